@@ -0,0 +1,259 @@
+use crate::{
+    minesweeper::{
+        Count,
+        Dim,
+    },
+    paths,
+};
+use std::{
+    fs,
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+    str::FromStr,
+};
+use winit::keyboard::KeyCode;
+
+/// Name of the config file inside the config directory.
+pub const CONFIG_FILE_NAME: &str = "config.txt";
+/// Theme drawn with the built-in skin.
+const BUILT_IN_THEME: &str = "classic";
+/// Name of the directory inside the config directory holding the skins that can be picked as
+/// themes, one directory each.
+const SKINS_DIR_NAME: &str = "skins";
+
+/// Keys bound to the actions that have a keyboard shortcut. Undo and redo are pressed along with
+/// Ctrl.
+#[derive(Debug, Clone, Copy)]
+pub struct Keybindings {
+    pub probabilities: KeyCode,
+    pub hint: KeyCode,
+    pub undo: KeyCode,
+    pub redo: KeyCode,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self {
+            probabilities: KeyCode::KeyP,
+            hint: KeyCode::KeyH,
+            undo: KeyCode::KeyZ,
+            redo: KeyCode::KeyY,
+        }
+    }
+}
+
+impl Keybindings {
+    /// Rebinds the actions listed in the given [str], leaving the rest as they are. The [str] is a
+    /// comma separated list of actions and keys such as `hint:H, undo:U`.
+    /// Returns an error if an action or key is unknown or if two actions end up on the same key.
+    fn rebind(&mut self, bindings: &str) -> Result<(), String> {
+        for binding in bindings.split(',').map(str::trim).filter(|b| !b.is_empty()) {
+            let (action, key) = binding
+                .split_once(':')
+                .ok_or_else(|| format!("expected an action and a key: {binding}"))?;
+            let key =
+                key_from_name(key.trim()).ok_or_else(|| format!("unknown key: {}", key.trim()))?;
+            match action.trim() {
+                "probabilities" => self.probabilities = key,
+                "hint" => self.hint = key,
+                "undo" => self.undo = key,
+                "redo" => self.redo = key,
+                action => return Err(format!("unknown action: {action}")),
+            }
+        }
+        // Undo and redo take Ctrl but the other actions don't check for it, so no key can be shared
+        let actions = [
+            ("probabilities", self.probabilities),
+            ("hint", self.hint),
+            ("undo", self.undo),
+            ("redo", self.redo),
+        ];
+        for (i, (first, first_key)) in actions.iter().enumerate() {
+            if let Some((second, _)) = actions[i + 1..].iter().find(|(_, key)| key == first_key) {
+                return Err(format!("{first} and {second} are bound to the same key"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the [KeyCode] of the key with the given name: a letter, a digit, or F1 to F12.
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    Some(match name.to_ascii_uppercase().as_str() {
+        "A" => KeyCode::KeyA,
+        "B" => KeyCode::KeyB,
+        "C" => KeyCode::KeyC,
+        "D" => KeyCode::KeyD,
+        "E" => KeyCode::KeyE,
+        "F" => KeyCode::KeyF,
+        "G" => KeyCode::KeyG,
+        "H" => KeyCode::KeyH,
+        "I" => KeyCode::KeyI,
+        "J" => KeyCode::KeyJ,
+        "K" => KeyCode::KeyK,
+        "L" => KeyCode::KeyL,
+        "M" => KeyCode::KeyM,
+        "N" => KeyCode::KeyN,
+        "O" => KeyCode::KeyO,
+        "P" => KeyCode::KeyP,
+        "Q" => KeyCode::KeyQ,
+        "R" => KeyCode::KeyR,
+        "S" => KeyCode::KeyS,
+        "T" => KeyCode::KeyT,
+        "U" => KeyCode::KeyU,
+        "V" => KeyCode::KeyV,
+        "W" => KeyCode::KeyW,
+        "X" => KeyCode::KeyX,
+        "Y" => KeyCode::KeyY,
+        "Z" => KeyCode::KeyZ,
+        "0" => KeyCode::Digit0,
+        "1" => KeyCode::Digit1,
+        "2" => KeyCode::Digit2,
+        "3" => KeyCode::Digit3,
+        "4" => KeyCode::Digit4,
+        "5" => KeyCode::Digit5,
+        "6" => KeyCode::Digit6,
+        "7" => KeyCode::Digit7,
+        "8" => KeyCode::Digit8,
+        "9" => KeyCode::Digit9,
+        "F1" => KeyCode::F1,
+        "F2" => KeyCode::F2,
+        "F3" => KeyCode::F3,
+        "F4" => KeyCode::F4,
+        "F5" => KeyCode::F5,
+        "F6" => KeyCode::F6,
+        "F7" => KeyCode::F7,
+        "F8" => KeyCode::F8,
+        "F9" => KeyCode::F9,
+        "F10" => KeyCode::F10,
+        "F11" => KeyCode::F11,
+        "F12" => KeyCode::F12,
+        _ => return None,
+    })
+}
+
+/// Defaults read from the config file, each along with the line it was read from so problems
+/// with it can point there. Values missing from the file are [None].
+#[derive(Debug, Default)]
+pub struct Config {
+    pub width: Option<(Dim, usize)>,
+    pub height: Option<(Dim, usize)>,
+    pub mines: Option<(Count, usize)>,
+    /// How many screen pixels each pixel of the textures takes up when the window opens.
+    pub scale: Option<(u32, usize)>,
+    /// Whether the game is only scaled by whole numbers.
+    pub integer_scaling: Option<(bool, usize)>,
+    /// Directory of the skin picked as the theme, [None] for the built-in one.
+    pub skin: Option<(PathBuf, usize)>,
+    pub keybindings: Keybindings,
+    /// Whether left clicking a flag turns it into a question mark.
+    pub question_marks: Option<(bool, usize)>,
+}
+
+/// Loads the config file from the config directory. Returns the default [Config] if there is no
+/// config file.
+/// Returns an error naming the file and line if the config file can't be read or is invalid.
+pub fn load() -> Result<Config, String> {
+    let Some(dir) = paths::config_dir() else {
+        return Ok(Config::default());
+    };
+    let path = dir.join(CONFIG_FILE_NAME);
+    match fs::read_to_string(&path) {
+        Ok(contents) => parse(&contents, &path),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(err) => Err(format!("could not read {}: {err}", path.display())),
+    }
+}
+
+/// Parses the contents of the config file at the given path. Every line is a `key = value` pair,
+/// with empty lines and lines starting with `#` ignored.
+/// Returns an error naming the file and line if a line is invalid.
+fn parse(contents: &str, path: &Path) -> Result<Config, String> {
+    /// Parses the value of the given key, keeping the number of the line it is on.
+    fn value<T: FromStr>(
+        key: &str,
+        value: &str,
+        line: usize,
+    ) -> Result<Option<(T, usize)>, String> {
+        match value.parse() {
+            Ok(value) => Ok(Some((value, line))),
+            Err(_) => Err(format!("invalid value for {key}: {value}")),
+        }
+    }
+
+    let mut config = Config::default();
+    for (idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let number = idx + 1;
+        let result = match line.split_once('=') {
+            Some((key, val)) => match (key.trim(), val.trim()) {
+                (key @ "width", val) => value(key, val, number).map(|val| config.width = val),
+                (key @ "height", val) => value(key, val, number).map(|val| config.height = val),
+                (key @ "mines", val) => value(key, val, number).map(|val| config.mines = val),
+                ("scale", "0") => Err("scale must be greater than 0".to_string()),
+                (key @ "scale", val) => value(key, val, number).map(|val| config.scale = val),
+                (key @ "integer_scaling", val) => {
+                    value(key, val, number).map(|val| config.integer_scaling = val)
+                }
+                ("theme", BUILT_IN_THEME) => {
+                    config.skin = None;
+                    Ok(())
+                }
+                ("theme", val) => {
+                    let skins_dir = path.with_file_name(SKINS_DIR_NAME);
+                    let dir = skins_dir.join(val);
+                    if dir.is_dir() {
+                        config.skin = Some((dir, number));
+                        Ok(())
+                    } else {
+                        Err(format!(
+                            "unknown theme: {val}, expected {BUILT_IN_THEME} or a skin in {}",
+                            skins_dir.display()
+                        ))
+                    }
+                }
+                ("keybindings", val) => config.keybindings.rebind(val),
+                (key @ "question_marks", val) => {
+                    value(key, val, number).map(|val| config.question_marks = val)
+                }
+                (key, _) => Err(format!("unknown key: {key}")),
+            },
+            None => Err(format!("expected key = value: {line}")),
+        };
+        result.map_err(|err| format!("{} line {number}: {err}", path.display()))?;
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_cannot_share_a_key() {
+        let mut keybindings = Keybindings::default();
+        assert!(keybindings.rebind("hint:Q, undo:U").is_ok());
+        assert_eq!(
+            (keybindings.hint, keybindings.undo),
+            (KeyCode::KeyQ, KeyCode::KeyU)
+        );
+        for bindings in [
+            "probabilities:Z",
+            "probabilities:Y",
+            "hint:Z",
+            "undo:P",
+            "redo:Z",
+        ] {
+            assert!(
+                Keybindings::default().rebind(bindings).is_err(),
+                "{bindings}"
+            );
+        }
+    }
+}
@@ -1,7 +1,17 @@
+mod config;
 mod main_window_graphics;
 mod minesweeper;
+mod paths;
+mod records;
+mod replay;
+mod save;
+mod screenshot;
+mod settings;
+mod solver;
 mod starting_params;
+mod stats;
 
+use anyhow::Context;
 use pollster::FutureExt;
 use std::sync::Arc;
 use winit::{
@@ -10,6 +20,7 @@ use winit::{
     event_loop,
     keyboard::{
         KeyCode,
+        ModifiersState,
         PhysicalKey,
     },
     window::{
@@ -19,8 +30,17 @@ use winit::{
     },
 };
 
+/// Longest time between window updates while a replay is playing, so the timer keeps up.
+const PLAYBACK_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+/// How far the arrow keys seek in a replay.
+const PLAYBACK_SEEK_STEP: std::time::Duration = std::time::Duration::from_secs(5);
+/// How many cells the arrow keys pan the camera by.
+const CAMERA_PAN_STEP: f32 = 2.0;
+
 /// The State of a  Minesweeper game process.
 struct State<'a> {
+    /// Kept to create surfaces for windows opened later, like the settings window.
+    instance: wgpu::Instance,
     surface: wgpu::Surface<'a>,
     device: wgpu::Device,
     queue: wgpu::Queue,
@@ -29,8 +49,36 @@ struct State<'a> {
     main_window_graphics: main_window_graphics::MainWindowGraphics,
     game: minesweeper::Game,
     cursor_pos: cgmath::Vector2<f32>,
+    /// Position of the cursor in the window, which the camera zooms in on.
+    window_cursor_pos: winit::dpi::PhysicalPosition<f64>,
+    /// Whether the middle mouse button is held down to pan the camera.
+    dragging_camera: bool,
     left_mouse_down: bool,
+    modifiers: ModifiersState,
     game_start_time: std::time::Instant,
+    /// When the current game ended, if it has.
+    game_end_time: Option<std::time::Instant>,
+    /// Whether hidden cells are tinted by their chance of being a mine.
+    show_probabilities: bool,
+    /// Cell suggested by the last hint, highlighted until it is revealed.
+    hint: Option<minesweeper::Pos>,
+    /// Whether the current game set a new record, celebrated by tinting the timer until a reset.
+    new_record: bool,
+    /// Records the inputs made this game so they can be saved once it ends.
+    recorder: replay::Recorder,
+    /// Replay driving the game instead of the mouse, if one is being played back.
+    playback: Option<replay::Playback>,
+    keybindings: config::Keybindings,
+    /// Scale the window was opened at, used again if it has to be opened again.
+    scale: Option<u32>,
+    /// Watches the files of the skin for changes if it was loaded from a directory.
+    skin_watcher: Option<main_window_graphics::SkinWatcher>,
+    /// When the timer or replay asked the event loop to wake up, kept while it is set to wake up
+    /// earlier to check the skin.
+    resume_time: Option<std::time::Instant>,
+    /// Control flow last set to wake up for both the skin and [State::resume_time].
+    skin_control_flow: Option<event_loop::ControlFlow>,
+    settings_window: Option<SettingsWindow<'a>>,
     // The window must be declared after the surface so
     // it gets dropped after it as the surface contains
     // unsafe references to the window's resources.
@@ -40,7 +88,16 @@ struct State<'a> {
 impl<'a> State<'a> {
     /// Creates a new State.
     /// It is async as creating some of the wgpu types requires async code.
-    fn new(window: Arc<Window>, minesweeper_game: minesweeper::Game) -> Self {
+    /// Fails if there is no GPU, or software renderer, that can draw to the window.
+    fn new(
+        window: Arc<Window>,
+        minesweeper_game: minesweeper::Game,
+        playback: Option<replay::Playback>,
+        keybindings: config::Keybindings,
+        scale: Option<u32>,
+        skin: main_window_graphics::Skin,
+        skin_watcher: Option<main_window_graphics::SkinWatcher>,
+    ) -> anyhow::Result<Self> {
         let size = window.inner_size();
 
         // The instance is a handle to our GPU
@@ -54,17 +111,21 @@ impl<'a> State<'a> {
         });
 
         // Handle for the window
-        let surface = instance.create_surface(window.clone()).unwrap();
-
-        // Adapter for instance
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .block_on()
-            .unwrap();
+        let surface = instance.create_surface(window.clone())?;
+
+        // Adapter for instance, settling for a software renderer if there is no GPU
+        let request_adapter = |force_fallback_adapter| {
+            instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::default(),
+                    compatible_surface: Some(&surface),
+                    force_fallback_adapter,
+                })
+                .block_on()
+        };
+        let adapter = request_adapter(false)
+            .or_else(|_| request_adapter(true))
+            .context("No GPU to draw the window with (--screenshot works without one)")?;
 
         // Adapter provides device for allocating GPU memory and queue editing GPU memory
         let (device, queue) = adapter
@@ -82,7 +143,7 @@ impl<'a> State<'a> {
                 trace: wgpu::Trace::Off,
             })
             .block_on()
-            .unwrap();
+            .context("Could not create a device to draw the window with")?;
 
         let surface_caps = surface.get_capabilities(&adapter);
 
@@ -111,10 +172,12 @@ impl<'a> State<'a> {
             &queue,
             config.format,
             &minesweeper_game,
+            skin,
         );
 
-        Self {
+        Ok(Self {
             window,
+            instance,
             surface,
             device,
             queue,
@@ -122,10 +185,25 @@ impl<'a> State<'a> {
             size,
             main_window_graphics,
             cursor_pos: cgmath::Vector2::new(0.0, 0.0),
+            window_cursor_pos: winit::dpi::PhysicalPosition::new(0.0, 0.0),
+            dragging_camera: false,
             left_mouse_down: false,
+            modifiers: ModifiersState::empty(),
             game: minesweeper_game,
             game_start_time: std::time::Instant::now(),
-        }
+            game_end_time: None,
+            show_probabilities: false,
+            hint: None,
+            new_record: false,
+            recorder: replay::Recorder::start(),
+            playback,
+            keybindings,
+            scale,
+            skin_watcher,
+            resume_time: None,
+            skin_control_flow: None,
+            settings_window: None,
+        })
     }
 
     /// Handles updating the State with a new window size.
@@ -139,16 +217,226 @@ impl<'a> State<'a> {
         }
     }
 
+    /// Returns how long the current game has been running, according to the replay if one is being
+    /// played back.
+    fn elapsed(&self) -> std::time::Duration {
+        match &self.playback {
+            Some(playback) => playback.game_time(),
+            None if self.game.game_state == minesweeper::GameState::BeforeGame => {
+                std::time::Duration::ZERO
+            }
+            None => self
+                .game_end_time
+                .unwrap_or_else(std::time::Instant::now)
+                .duration_since(self.game_start_time),
+        }
+    }
+
+    /// Continues timing a game that had been running for the given time and updates the window
+    /// to match the game.
+    fn resume_timer(
+        &mut self,
+        elapsed: std::time::Duration,
+        event_loop: &event_loop::ActiveEventLoop,
+    ) {
+        let now = std::time::Instant::now();
+        self.game_start_time = now.checked_sub(elapsed).unwrap_or(now);
+        if self.game.game_state.is_after_game() {
+            self.game_end_time = Some(now);
+        }
+        self.update_display(main_window_graphics::Display::Timer);
+        self.main_window_graphics
+            .update_face(main_window_graphics::face_from_game_state(
+                false,
+                false,
+                &self.game.game_state,
+            ));
+        if self.game.game_state == minesweeper::GameState::DuringGame {
+            event_loop.set_control_flow(event_loop::ControlFlow::WaitUntil(
+                self.game_start_time + std::time::Duration::from_secs(elapsed.as_secs() + 1),
+            ));
+        }
+    }
+
+    /// Reloads the skin if its files changed and are due to be checked, redrawing the windows with
+    /// it. Keeps the old skin if the new one can't be loaded.
+    fn reload_skin(&mut self) {
+        let Some(skin_watcher) = &mut self.skin_watcher else {
+            return;
+        };
+        match skin_watcher.check() {
+            Some(Ok(skin)) => {
+                if let Some(settings_window) = &mut self.settings_window {
+                    settings_window.set_skin(&self.device, &self.queue, skin.clone());
+                }
+                self.main_window_graphics
+                    .set_skin(&self.device, &self.queue, skin);
+                self.window.request_redraw();
+                println!("Skin reloaded");
+            }
+            Some(Err(err)) => log::error!("Failed to reload skin: {err:#}"),
+            None => {}
+        }
+    }
+
+    /// Sets the event loop to also wake up when the skin is due to be checked, remembering when
+    /// it was set to wake up otherwise.
+    fn schedule_skin_check(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        let Some(skin_watcher) = &self.skin_watcher else {
+            return;
+        };
+        // Anything else that changed the control flow since it was last set here sets the new
+        // time to wake up
+        let control_flow = event_loop.control_flow();
+        if Some(control_flow) != self.skin_control_flow {
+            self.resume_time = match control_flow {
+                event_loop::ControlFlow::WaitUntil(time) => Some(time),
+                _ => None,
+            };
+        }
+        let next_check = skin_watcher.next_check();
+        let control_flow = event_loop::ControlFlow::WaitUntil(
+            self.resume_time
+                .map_or(next_check, |time| time.min(next_check)),
+        );
+        event_loop.set_control_flow(control_flow);
+        self.skin_control_flow = Some(control_flow);
+    }
+
+    /// Saves the game if it is in progress so it can be resumed with `--resume`.
+    fn save_game(&self) {
+        if self.playback.is_some() || self.game.game_state != minesweeper::GameState::DuringGame {
+            return;
+        }
+        let result = save::default_path().and_then(|path| {
+            save::save(&self.game, self.elapsed(), &path)?;
+            Ok(path)
+        });
+        match result {
+            Ok(path) => println!(
+                "Game saved to {}, continue it with --resume",
+                path.display()
+            ),
+            Err(err) => log::error!("Failed to save game: {err:#}"),
+        }
+    }
+
     /// Updates the [Display] in the main window based on self's internal data.
     fn update_display(&mut self, display: main_window_graphics::Display) {
         use main_window_graphics::Display;
         let new_val = match display {
-            Display::Timer => self.game_start_time.elapsed().as_secs() as i32,
+            Display::Timer => self.elapsed().as_secs() as i32,
             Display::MinesUnflagged => self.game.total_mines as i32 - self.game.flags as i32,
         };
         self.main_window_graphics.update_display(display, new_val);
     }
 
+    /// Updates the probability overlay to match the game if it is shown and highlights the hinted
+    /// cell if it is still hidden.
+    fn update_overlay(&mut self) {
+        self.hint = self.hint.filter(|pos| {
+            self.game.game_state == minesweeper::GameState::DuringGame
+                && !self.game.get_image_at(*pos).shown()
+        });
+        if !self.show_probabilities {
+            self.main_window_graphics.clear_overlay();
+        } else if let Some(probabilities) = self.game.mine_probabilities() {
+            self.main_window_graphics.show_probabilities(&probabilities);
+        } else {
+            log::warn!("Too many possibilities to compute mine probabilities");
+            self.main_window_graphics.clear_overlay();
+        }
+        if let Some(pos) = self.hint {
+            self.main_window_graphics.highlight_cell(pos);
+        }
+        if self.new_record {
+            self.main_window_graphics
+                .highlight_display(main_window_graphics::Display::Timer);
+        }
+    }
+
+    /// Updates the lifetime stats with the change from the given game state to the current one.
+    /// Games in practice mode don't count since undoing can end them more than once.
+    fn update_stats(&self, old_game_state: minesweeper::GameState) {
+        use minesweeper::GameState;
+        let new_game_state = self.game.game_state;
+        if self.game.practice || old_game_state == new_game_state {
+            return;
+        }
+        let mut stats = match stats::Stats::load() {
+            Ok(stats) => stats,
+            Err(err) => {
+                log::error!("Failed to load stats: {err:#}");
+                return;
+            }
+        };
+        let difficulty_stats = stats.get_mut(records::Difficulty::of(&self.game));
+        if old_game_state == GameState::BeforeGame {
+            difficulty_stats.start();
+        }
+        match (old_game_state, new_game_state) {
+            (GameState::DuringGame, GameState::BeforeGame) => difficulty_stats.abandon(),
+            (_, GameState::Victory) => difficulty_stats.win(self.elapsed(), self.game.three_bv()),
+            (_, GameState::Loss) => difficulty_stats.lose(),
+            _ => {}
+        }
+        if let Err(err) = stats.save() {
+            log::error!("Failed to save stats: {err:#}");
+        }
+    }
+
+    /// Adds the game that was just won to the records unless hints or practice mode were used.
+    /// Celebrates if it made it into the records.
+    fn add_record(&mut self) {
+        if !records::counts(&self.game) {
+            return;
+        }
+        let difficulty = records::Difficulty::of(&self.game);
+        let record = records::Record::new(self.elapsed(), self.game.three_bv(), self.game.seed);
+        let mut records = match records::Records::load() {
+            Ok(records) => records,
+            Err(err) => {
+                log::error!("Failed to load records: {err:#}");
+                return;
+            }
+        };
+        let Some(rank) = records.insert(difficulty, record) else {
+            return;
+        };
+        if let Err(err) = records.save() {
+            log::error!("Failed to save records: {err:#}");
+            return;
+        }
+        println!("New record: #{rank} for {difficulty}");
+        self.new_record = true;
+        self.window
+            .set_title(&format!("Minesweeper - New record #{rank}!"));
+        self.update_overlay();
+        self.window.request_redraw();
+    }
+
+    /// Asks the game for a hint, highlights the suggested cell, and prints why it was suggested.
+    fn show_hint(&mut self) {
+        use minesweeper::Hint;
+        let Some(hint) = self.game.hint() else {
+            return;
+        };
+        match hint {
+            Hint::Safe(finding) => println!("Hint: {finding}"),
+            Hint::LeastRisky {
+                pos,
+                mine_probability,
+            } => println!(
+                "Hint: no cell is provably safe, {pos:?} is the least likely to be a mine at \
+                {:.1}%",
+                mine_probability * 100.0
+            ),
+        }
+        self.hint = Some(hint.pos());
+        self.update_overlay();
+        self.window.request_redraw();
+    }
+
     /// Updates the face textures if needed based on the change in mouse position, left_mouse_down,
     /// and game_state.
     /// Returns whether an update was made.
@@ -257,15 +545,14 @@ impl<'a> State<'a> {
         grid_updated || face_updated
     }
 
-    /// Updates the position of the cursor and updates the window if needed.
-    fn move_cursor(&mut self, new_pos: &winit::dpi::PhysicalPosition<f64>) {
-        // Calculate new position
-        let scaling_x = self.main_window_graphics.scaling_x();
-        let scaling_y = self.main_window_graphics.scaling_y();
-        let new_pos_x = (new_pos.x as f32 / self.size.width as f32 - 0.5) / scaling_x * 2.0;
-        let new_pos_y = (new_pos.y as f32 / self.size.height as f32 - 0.5) / scaling_y * -2.0;
-        let new_pos = cgmath::vec2(new_pos_x, new_pos_y);
+    /// Converts a position in the window to a position relative to the game, with both axes from
+    /// -1 to 1 over the game.
+    fn to_game_pos(&self, pos: &winit::dpi::PhysicalPosition<f64>) -> cgmath::Vector2<f32> {
+        self.main_window_graphics.window_to_game(pos, &self.size)
+    }
 
+    /// Updates the position of the cursor and updates the window if needed.
+    fn move_cursor(&mut self, new_pos: cgmath::Vector2<f32>) {
         // Update position in self but keep old position
         let old_pos = self.cursor_pos;
         self.cursor_pos = new_pos;
@@ -314,21 +601,25 @@ impl<'a> State<'a> {
                 GameState,
             };
 
-            // Start game if before game
+            // Perform the click on the cell, get the list of cells to update, and update the grid
+            // using the updates.
+            let starting = self.game.game_state == GameState::BeforeGame;
+            let updates = self.game.left_click(pos);
+            self.main_window_graphics.update_grid(&updates);
+
+            // Start game if before game, after placing the mines so that doesn't count
             // Set start time to now and set control flow to send an event in 1 second to update the
             // timer
-            if let GameState::BeforeGame = self.game.game_state {
+            if starting {
                 self.game_start_time = std::time::Instant::now();
                 event_loop.set_control_flow(event_loop::ControlFlow::WaitUntil(
                     self.game_start_time + std::time::Duration::from_secs_f32(1.0),
                 ));
+                if self.game.needs_guessing {
+                    println!("No layout without guessing found, this board may need a guess");
+                }
             }
 
-            // Perform the click on the cell, get the list of cells to update, and update the grid
-            // using the updates.
-            let updates = self.game.left_click(pos);
-            self.main_window_graphics.update_grid(&updates);
-
             // If the game is not running, stops event loop from resuming after the timer
             // No longer need it to resume as the timer should have stopped running
             if !matches!(self.game.game_state, GameState::DuringGame) {
@@ -346,17 +637,43 @@ impl<'a> State<'a> {
             // time.
             if !updates.is_empty() && let GameState::Victory = self.game.game_state {
                 self.update_display(main_window_graphics::Display::MinesUnflagged);
-                let game_duration_ms = self.game_start_time.elapsed().as_millis();
+                let game_duration_ms = self.elapsed().as_millis();
                 let game_duration_seconds = game_duration_ms / 1000;
                 println!(
                     "Game duration: {}.{} seconds",
                     game_duration_seconds,
                     game_duration_ms % 1000
                 );
+                if self.game.is_hinted() {
+                    println!(
+                        "Used {} hints, so this game doesn't count toward records",
+                        self.game.hints
+                    );
+                }
+            }
+
+            // If the game was lost by this click, print how likely the clicked cell was a mine
+            if !updates.is_empty()
+                && let GameState::Loss = self.game.game_state
+                && let Some(grade) = self.game.grade_loss()
+            {
+                println!(
+                    "Chance the clicked cell was a mine: {:.1}% ({})",
+                    grade.mine_probability * 100.0,
+                    if grade.forced_guess {
+                        "forced guess"
+                    } else {
+                        "a safe cell was available"
+                    }
+                );
             }
         } else if face_pressed { // Press face
             // Reset "everything"
             self.game.reset();
+            if self.new_record {
+                self.new_record = false;
+                self.window.set_title("Minesweeper");
+            }
             self.main_window_graphics.reset_grid();
             self.game_start_time = std::time::Instant::now();
             self.update_display(main_window_graphics::Display::Timer);
@@ -367,6 +684,7 @@ impl<'a> State<'a> {
         // Update face and grid and request redraw
         // Redraw will always be needed to at least update face
         self.update_grid_and_face(self.cursor_pos, true, old_game_state);
+        self.update_overlay();
         self.window.request_redraw();
     }
 
@@ -383,17 +701,354 @@ impl<'a> State<'a> {
             if let Some(update) = update {
                 self.main_window_graphics.update_grid(&[update]);
                 self.update_display(main_window_graphics::Display::MinesUnflagged);
+                self.update_overlay();
                 self.window.request_redraw();
             }
         }
     }
 
+    /// Undoes or redoes a move and updates the window to match. Restarts the timer if the game
+    /// was resumed and stops it if the game was ended again.
+    fn undo_or_redo(&mut self, redo: bool, event_loop: &event_loop::ActiveEventLoop) {
+        use minesweeper::GameState;
+        let old_game_state = self.game.game_state;
+        let updates = if redo {
+            self.game.redo()
+        } else {
+            self.game.undo()
+        };
+        if updates.is_empty() {
+            return;
+        }
+        self.main_window_graphics.update_grid(&updates);
+        self.update_display(main_window_graphics::Display::MinesUnflagged);
+        match (old_game_state, self.game.game_state) {
+            (GameState::Victory | GameState::Loss, GameState::DuringGame) => {
+                self.update_display(main_window_graphics::Display::Timer);
+                event_loop.set_control_flow(event_loop::ControlFlow::WaitUntil(
+                    std::time::Instant::now() + std::time::Duration::from_secs_f32(1.0),
+                ));
+            }
+            (GameState::DuringGame, GameState::Victory | GameState::Loss) => {
+                event_loop.set_control_flow(event_loop::ControlFlow::Wait);
+            }
+            _ => {}
+        }
+        self.update_grid_and_face(self.cursor_pos, self.left_mouse_down, old_game_state);
+        self.update_overlay();
+        self.window.request_redraw();
+    }
+
+    /// Performs the given input on the game and updates the window to match.
+    fn handle_event(&mut self, event: replay::Event, event_loop: &event_loop::ActiveEventLoop) {
+        use replay::Event;
+        let was_after_game = self.game.game_state.is_after_game();
+        match event {
+            Event::CursorMoved([x, y]) => self.move_cursor(cgmath::vec2(x, y)),
+            Event::LeftPressed => self.left_mouse_down(),
+            Event::LeftClick(_) | Event::Chord(_) | Event::LeftReleased => {
+                self.left_mouse_released(event_loop)
+            }
+            Event::RightClick(_) => self.right_mouse_down(),
+            Event::Undo => self.undo_or_redo(false, event_loop),
+            Event::Redo => self.undo_or_redo(true, event_loop),
+        }
+
+        // Stop the clock when the game ends and restart it if the game is resumed. Print how the
+        // game went once it ends.
+        if !self.game.game_state.is_after_game() {
+            self.game_end_time = None;
+        } else if !was_after_game {
+            self.game_end_time = Some(std::time::Instant::now());
+            println!("{}", self.game.metrics(self.elapsed()));
+        }
+    }
+
+    /// Records the given input, performs it, and saves the replay if it ended the game. Starts a
+    /// new recording if the input reset the game.
+    fn record_event(&mut self, event: replay::Event, event_loop: &event_loop::ActiveEventLoop) {
+        use minesweeper::GameState;
+        let old_game_state = self.game.game_state;
+        self.recorder.record(event);
+        self.handle_event(event, event_loop);
+        self.update_stats(old_game_state);
+
+        if !old_game_state.is_after_game()
+            && self.game.game_state.is_after_game()
+            && let Some(replay) = self.recorder.replay(&self.game)
+        {
+            match replay.save() {
+                Ok(path) => println!("Replay saved to {}", path.display()),
+                Err(err) => log::error!("Failed to save replay: {err:#}"),
+            }
+        }
+        if !old_game_state.is_after_game() && self.game.game_state == GameState::Victory {
+            self.add_record();
+        }
+        if old_game_state != GameState::BeforeGame && self.game.game_state == GameState::BeforeGame
+        {
+            self.recorder = replay::Recorder::start();
+        }
+    }
+
+    /// Performs the given event from the replay being played back, keeping track of when the
+    /// recorded game started and ended.
+    fn play_event(
+        &mut self,
+        (time, event): (std::time::Duration, replay::Event),
+        event_loop: &event_loop::ActiveEventLoop,
+    ) {
+        use minesweeper::GameState;
+        let old_game_state = self.game.game_state;
+        self.handle_event(event, event_loop);
+        let new_game_state = self.game.game_state;
+        let Some(playback) = &mut self.playback else {
+            return;
+        };
+        if old_game_state == GameState::BeforeGame && new_game_state == GameState::DuringGame {
+            playback.game_start = Some(time);
+        }
+        if new_game_state.is_after_game() {
+            playback.game_end = playback.game_end.or(Some(time));
+        } else {
+            playback.game_end = None;
+        }
+    }
+
+    /// Performs every event of the replay being played back that is due, updates the window to
+    /// match, and schedules the next update.
+    fn advance_playback(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        let Some(playback) = &mut self.playback else {
+            return;
+        };
+        let was_finished = playback.is_finished();
+        for event in playback.due_events() {
+            self.play_event(event, event_loop);
+        }
+        if let Some(playback) = &self.playback
+            && playback.is_finished()
+            && !was_finished
+        {
+            self.update_playback_title();
+        }
+        self.finish_playback_update(event_loop);
+    }
+
+    /// Moves the replay being played back to the given position by performing every event up to
+    /// it on a fresh game.
+    fn seek_playback(
+        &mut self,
+        position: std::time::Duration,
+        event_loop: &event_loop::ActiveEventLoop,
+    ) {
+        let Some(playback) = &mut self.playback else {
+            return;
+        };
+        let events = playback.seek(position);
+        self.game.reset();
+        self.main_window_graphics.reset_grid();
+        self.left_mouse_down = false;
+        self.hint = None;
+        self.main_window_graphics
+            .update_face(main_window_graphics::face_from_game_state(
+                false,
+                false,
+                &self.game.game_state,
+            ));
+        self.update_display(main_window_graphics::Display::MinesUnflagged);
+        for event in events {
+            self.play_event(event, event_loop);
+        }
+        self.update_playback_title();
+        self.finish_playback_update(event_loop);
+    }
+
+    /// Updates the window after events from the replay were performed and schedules the next
+    /// update. Events set their own control flow for the timer, so it is always overridden.
+    fn finish_playback_update(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        let Some(playback) = &self.playback else {
+            return;
+        };
+        let control_flow = match playback.next_deadline() {
+            Some(deadline) => event_loop::ControlFlow::WaitUntil(
+                deadline.min(std::time::Instant::now() + PLAYBACK_REFRESH_INTERVAL),
+            ),
+            None => event_loop::ControlFlow::Wait,
+        };
+        event_loop.set_control_flow(control_flow);
+        self.main_window_graphics
+            .set_cursor_marker(Some(self.cursor_pos));
+        self.update_display(main_window_graphics::Display::Timer);
+        self.update_overlay();
+        self.window.request_redraw();
+    }
+
+    /// Shows the state of the replay being played back in the window title.
+    fn update_playback_title(&self) {
+        let Some(playback) = &self.playback else {
+            return;
+        };
+        let state = if playback.is_playing() {
+            "playing"
+        } else if playback.is_finished() {
+            "finished"
+        } else {
+            "paused"
+        };
+        self.window.set_title(&format!(
+            "Minesweeper replay - {state} at {}x - {:.1}s / {:.1}s",
+            playback.speed(),
+            playback.position().as_secs_f32(),
+            playback.duration().as_secs_f32(),
+        ));
+    }
+
+    /// Handles keyboard controls for the replay being played back. Ignores mouse inputs since
+    /// the replay controls the game.
+    /// Returns whether the event matched any of its cases.
+    fn playback_input(
+        &mut self,
+        event: &WindowEvent,
+        event_loop: &event_loop::ActiveEventLoop,
+    ) -> bool {
+        let key = match event {
+            WindowEvent::CursorMoved { .. } | WindowEvent::MouseInput { .. } => return true,
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(key),
+                        ..
+                    },
+                ..
+            } => *key,
+            _ => return false,
+        };
+        let Some(playback) = &mut self.playback else {
+            return false;
+        };
+        let position = playback.position();
+        match key {
+            KeyCode::Space => playback.toggle_playing(),
+            KeyCode::Period => {
+                if let Some(event) = playback.step() {
+                    self.play_event(event, event_loop);
+                }
+            }
+            KeyCode::ArrowUp => playback.change_speed(true),
+            KeyCode::ArrowDown => playback.change_speed(false),
+            KeyCode::ArrowLeft => {
+                self.seek_playback(position.saturating_sub(PLAYBACK_SEEK_STEP), event_loop)
+            }
+            KeyCode::ArrowRight => self.seek_playback(position + PLAYBACK_SEEK_STEP, event_loop),
+            KeyCode::Home => self.seek_playback(std::time::Duration::ZERO, event_loop),
+            KeyCode::KeyP => {
+                self.show_probabilities = !self.show_probabilities;
+            }
+            _ => return false,
+        }
+        self.update_playback_title();
+        self.finish_playback_update(event_loop);
+        true
+    }
+
+    /// Returns the event for releasing the left mouse button at the cursor's position.
+    fn left_release_event(&self) -> replay::Event {
+        use replay::Event;
+        match main_window_graphics::convert_to_over_grid(
+            self.game.width,
+            self.game.height,
+            self.cursor_pos,
+        ) {
+            Some(pos) if self.game.get_image_at(pos).shown() => Event::Chord(pos),
+            Some(pos) => Event::LeftClick(pos),
+            None => Event::LeftReleased,
+        }
+    }
+
+    /// Handles zooming the camera with the mouse wheel and panning it by dragging with the middle
+    /// mouse button or, unless a replay is being played back, with the arrow keys.
+    /// Returns whether the event was used up. Cursor movements never are.
+    fn camera_input(
+        &mut self,
+        event: &WindowEvent,
+        event_loop: &event_loop::ActiveEventLoop,
+    ) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let old_position = std::mem::replace(&mut self.window_cursor_pos, *position);
+                if self.dragging_camera {
+                    self.main_window_graphics
+                        .drag_camera(&old_position, position, &self.size);
+                    self.window.request_redraw();
+                }
+                return false;
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Middle,
+                ..
+            } => {
+                self.dragging_camera = *state == ElementState::Pressed;
+                return true;
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let scrolled = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32,
+                };
+                if scrolled == 0.0 {
+                    return true;
+                }
+                self.main_window_graphics.zoom_at(
+                    &self.window_cursor_pos,
+                    &self.size,
+                    scrolled.signum(),
+                );
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(key),
+                        ..
+                    },
+                ..
+            } if self.playback.is_none() => {
+                let (cols, rows) = match key {
+                    KeyCode::ArrowLeft => (1.0, 0.0),
+                    KeyCode::ArrowRight => (-1.0, 0.0),
+                    KeyCode::ArrowUp => (0.0, -1.0),
+                    KeyCode::ArrowDown => (0.0, 1.0),
+                    _ => return false,
+                };
+                self.main_window_graphics
+                    .pan_camera(cols * CAMERA_PAN_STEP, rows * CAMERA_PAN_STEP);
+            }
+            _ => return false,
+        }
+        // Whatever is under the cursor changed along with the camera
+        if self.playback.is_none() {
+            let pos = self.to_game_pos(&self.window_cursor_pos);
+            self.record_event(replay::Event::CursorMoved([pos.x, pos.y]), event_loop);
+        }
+        self.window.request_redraw();
+        true
+    }
+
     /// Handles user inputs to the window.
     /// Returns whether the event matched any of its cases.
     fn input(&mut self, event: &WindowEvent, event_loop: &event_loop::ActiveEventLoop) -> bool {
+        if self.camera_input(event, event_loop) {
+            return true;
+        }
+        if self.playback.is_some() {
+            return self.playback_input(event, event_loop);
+        }
         match event {
             WindowEvent::CursorMoved { position, .. } => {
-                self.move_cursor(position);
+                let pos = self.to_game_pos(position);
+                self.record_event(replay::Event::CursorMoved([pos.x, pos.y]), event_loop);
                 true
             }
             WindowEvent::MouseInput {
@@ -401,7 +1056,19 @@ impl<'a> State<'a> {
                 button: MouseButton::Right,
                 ..
             } => {
-                self.right_mouse_down();
+                if let Some(pos) = main_window_graphics::convert_to_over_grid(
+                    self.game.width,
+                    self.game.height,
+                    self.cursor_pos,
+                ) {
+                    self.record_event(replay::Event::RightClick(pos), event_loop);
+                } else if main_window_graphics::is_over_face(
+                    self.game.width,
+                    self.game.height,
+                    self.cursor_pos,
+                ) {
+                    self.open_settings(event_loop);
+                }
                 true
             }
             WindowEvent::MouseInput {
@@ -409,7 +1076,7 @@ impl<'a> State<'a> {
                 button: MouseButton::Left,
                 ..
             } => {
-                self.left_mouse_down();
+                self.record_event(replay::Event::LeftPressed, event_loop);
                 true
             }
             WindowEvent::MouseInput {
@@ -417,58 +1084,445 @@ impl<'a> State<'a> {
                 button: MouseButton::Left,
                 ..
             } => {
-                self.left_mouse_released(event_loop);
+                self.record_event(self.left_release_event(), event_loop);
+                true
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(key),
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } if *key == self.keybindings.probabilities => {
+                self.show_probabilities = !self.show_probabilities;
+                self.update_overlay();
+                self.window.request_redraw();
+                true
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+                true
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(key),
+                        ..
+                    },
+                ..
+            } if self.game.practice
+                && self.modifiers.control_key()
+                && (*key == self.keybindings.undo || *key == self.keybindings.redo) =>
+            {
+                let event = if *key == self.keybindings.redo {
+                    replay::Event::Redo
+                } else {
+                    replay::Event::Undo
+                };
+                self.record_event(event, event_loop);
+                true
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(key),
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } if *key == self.keybindings.hint => {
+                self.show_hint();
                 true
             }
             _ => false,
         }
     }
 
-    /// Render the game to the window.
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
+    /// Returns how many times larger than its native size the main window is, rounded down and at
+    /// least 1.
+    fn window_scale(&self) -> u32 {
+        let native_size = main_window_graphics::get_native_size(self.game.width, self.game.height);
+        (self.size.width / native_size.width)
+            .min(self.size.height / native_size.height)
+            .max(1)
+    }
+
+    /// Opens the settings window at the scale of the main window, or focuses it if it is already
+    /// open.
+    fn open_settings(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        if let Some(settings_window) = &self.settings_window {
+            settings_window.window.focus_window();
+            return;
+        }
+        let scale = self.window_scale();
+        let native_size = main_window_graphics::get_settings_native_size();
+        let window_attributes = WindowAttributes::default()
+            .with_title("Minesweeper settings")
+            .with_inner_size(winit::dpi::PhysicalSize::new(
+                native_size.width * scale,
+                native_size.height * scale,
+            ));
+        let window = match event_loop.create_window(window_attributes) {
+            Ok(window) => Arc::new(window),
+            Err(err) => {
+                log::error!("Failed to open settings window: {err}");
+                return;
+            }
+        };
+        match SettingsWindow::new(
+            window,
+            &self.instance,
+            &self.device,
+            &self.queue,
+            &self.config,
+            settings::Settings::of(&self.game),
+            self.main_window_graphics.skin().clone(),
+        ) {
+            Ok(mut settings_window) => {
+                settings_window.set_integer_scaling(self.main_window_graphics.integer_scaling());
+                self.settings_window = Some(settings_window);
+            }
+            Err(err) => log::error!("Failed to open settings window: {err}"),
+        }
+    }
 
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+    /// Handles events sent to the settings window. Closes it on escape or when cancelled and
+    /// applies the settings when asked to.
+    fn settings_input(&mut self, event: &WindowEvent, event_loop: &event_loop::ActiveEventLoop) {
+        let Some(settings_window) = &mut self.settings_window else {
+            return;
+        };
+        match event {
+            WindowEvent::CloseRequested
+            | WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::Escape),
+                        ..
+                    },
+                ..
+            } => self.settings_window = None,
+            WindowEvent::RedrawRequested => settings_window.render(&self.device, &self.queue),
+            WindowEvent::Resized(physical_size) => {
+                settings_window.resize(&self.device, *physical_size);
+            }
+            _ => match settings_window.input(event) {
+                Some(settings::Control::Apply) => self.apply_settings(event_loop),
+                Some(settings::Control::Cancel) => self.settings_window = None,
+                _ => {}
+            },
+        }
+    }
 
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
+    /// Closes the settings window and starts a new game with its settings. Resizes the grid and
+    /// the window to fit it at the same scale.
+    fn apply_settings(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        let Some(settings_window) = self.settings_window.take() else {
+            return;
+        };
 
+        // Count the game being replaced at its old difficulty before changing it
+        let old_game_state = self.game.game_state;
+        self.game.reset();
+        self.update_stats(old_game_state);
+        settings_window.settings.apply_to(&mut self.game);
+
+        if let Some(size) = self
+            .main_window_graphics
+            .resize_grid(&self.game, &self.window)
         {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    depth_slice: None,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
+            self.resize(size);
+        }
+        self.left_mouse_down = false;
+        self.hint = None;
+        if self.new_record {
+            self.new_record = false;
+            self.window.set_title("Minesweeper");
+        }
+        self.recorder = replay::Recorder::start();
+        self.game_start_time = std::time::Instant::now();
+        self.game_end_time = None;
+        self.update_display(main_window_graphics::Display::Timer);
+        self.update_display(main_window_graphics::Display::MinesUnflagged);
+        self.update_overlay();
+        event_loop.set_control_flow(event_loop::ControlFlow::Wait);
+        self.window.request_redraw();
+    }
 
+    /// Render the game to the window.
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        render_to_surface(&self.surface, &self.device, &self.queue, |render_pass| {
             self.main_window_graphics
-                .render(&mut render_pass, &self.device, &self.queue);
+                .render(render_pass, &self.device, &self.queue);
+        })
+    }
+}
+
+/// Clears the current texture of the given surface, draws to it with the given function, and
+/// presents it.
+fn render_to_surface(
+    surface: &wgpu::Surface,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    draw: impl FnOnce(&mut wgpu::RenderPass),
+) -> Result<(), wgpu::SurfaceError> {
+    let output = surface.get_current_texture()?;
+
+    let view = output
+        .texture
+        .create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Render Encoder"),
+    });
+
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        draw(&mut render_pass);
+    }
+
+    queue.submit(std::iter::once(encoder.finish()));
+    output.present();
+
+    Ok(())
+}
+
+/// The window for changing the size of the board and other settings, opened by right-clicking the
+/// face. Shares the device of the main window.
+struct SettingsWindow<'a> {
+    surface: wgpu::Surface<'a>,
+    config: wgpu::SurfaceConfiguration,
+    graphics: main_window_graphics::SettingsWindowGraphics,
+    /// Settings being edited, only applied to the game when the apply button is clicked.
+    settings: settings::Settings,
+    cursor_pos: cgmath::Vector2<f32>,
+    modifiers: ModifiersState,
+    /// Control under the cursor, if any.
+    hovered: Option<settings::Control>,
+    /// Button held down by the left mouse button, if any.
+    pressed: Option<settings::Control>,
+    // Declared after the surface for the same reason as in State
+    window: Arc<Window>,
+}
+
+impl<'a> SettingsWindow<'a> {
+    /// Creates the graphics for the given window showing the given settings. The window is drawn
+    /// like the main window, so it is configured the same way apart from its size.
+    fn new(
+        window: Arc<Window>,
+        instance: &wgpu::Instance,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        main_config: &wgpu::SurfaceConfiguration,
+        settings: settings::Settings,
+        skin: main_window_graphics::Skin,
+    ) -> Result<Self, wgpu::CreateSurfaceError> {
+        let size = window.inner_size();
+        let surface = instance.create_surface(window.clone())?;
+        let config = wgpu::SurfaceConfiguration {
+            width: size.width.max(1),
+            height: size.height.max(1),
+            ..main_config.clone()
+        };
+        surface.configure(device, &config);
+        let mut graphics = main_window_graphics::SettingsWindowGraphics::new(
+            device,
+            queue,
+            config.format,
+            &settings,
+            skin,
+        );
+        graphics.rescale(&size);
+        Ok(Self {
+            surface,
+            config,
+            graphics,
+            settings,
+            cursor_pos: cgmath::Vector2::new(0.0, 0.0),
+            modifiers: ModifiersState::empty(),
+            hovered: None,
+            pressed: None,
+            window,
+        })
+    }
+
+    /// Redraws the settings window with the given skin.
+    fn set_skin(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        skin: main_window_graphics::Skin,
+    ) {
+        self.graphics.set_skin(device, queue, skin);
+        self.refresh();
+    }
+
+    /// Sets whether the settings window is only scaled by whole numbers and rescales it to match.
+    fn set_integer_scaling(&mut self, integer_scaling: bool) {
+        self.graphics.set_integer_scaling(integer_scaling);
+        self.graphics.rescale(&self.window.inner_size());
+        self.window.request_redraw();
+    }
+
+    /// Handles updating the settings window with a new window size.
+    fn resize(&mut self, device: &wgpu::Device, new_size: winit::dpi::PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.config.width = new_size.width;
+            self.config.height = new_size.height;
+            self.surface.configure(device, &self.config);
+            self.graphics.rescale(&new_size);
+            self.window.request_redraw();
         }
+    }
 
-        self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+    /// Handles mouse inputs to the settings window. Left clicking a number raises it and right
+    /// clicking lowers it, by 10 while shift is held, as does scrolling over it.
+    /// Returns the button that was clicked, if any.
+    fn input(&mut self, event: &WindowEvent) -> Option<settings::Control> {
+        use settings::Control;
+        let step = if self.modifiers.shift_key() { 10 } else { 1 };
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_pos = self
+                    .graphics
+                    .window_to_game(position, &self.window.inner_size());
+                let hovered = main_window_graphics::settings_control_at(self.cursor_pos);
+                if hovered != self.hovered {
+                    self.hovered = hovered;
+                    self.refresh();
+                }
+            }
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                match self.hovered {
+                    Some(Control::Field(field)) => self.settings.adjust(field, step),
+                    Some(Control::Toggle(toggle)) => self.settings.toggle(toggle),
+                    button => self.pressed = button,
+                }
+                self.refresh();
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let pressed = self.pressed.take()?;
+                self.refresh();
+                return (Some(pressed) == self.hovered).then_some(pressed);
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Right,
+                ..
+            } => {
+                if let Some(Control::Field(field)) = self.hovered {
+                    self.settings.adjust(field, -step);
+                    self.refresh();
+                }
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let scrolled = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32,
+                };
+                if let Some(Control::Field(field)) = self.hovered
+                    && scrolled != 0.0
+                {
+                    self.settings.adjust(field, step * scrolled.signum() as i32);
+                    self.refresh();
+                }
+            }
+            _ => {}
+        }
+        None
+    }
 
-        Ok(())
+    /// Updates the graphics and title to match the settings and redraws the window.
+    fn refresh(&mut self) {
+        use settings::Control;
+        self.graphics
+            .update(&self.settings, self.hovered, self.pressed);
+        // The atlas has no text, so the hovered control is described in the title
+        let title = match self.hovered {
+            Some(control @ Control::Field(field)) => format!(
+                "Minesweeper settings - {}: {}",
+                control.description(),
+                self.settings.get(field)
+            ),
+            Some(control @ Control::Toggle(toggle)) => format!(
+                "Minesweeper settings - {}: {}",
+                control.description(),
+                if self.settings.is_on(toggle) {
+                    "on"
+                } else {
+                    "off"
+                }
+            ),
+            Some(control) => format!("Minesweeper settings - {}", control.description()),
+            None => "Minesweeper settings".into(),
+        };
+        self.window.set_title(&title);
+        self.window.request_redraw();
+    }
+
+    /// Renders the settings window, reconfiguring the surface if it was lost.
+    fn render(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let result = render_to_surface(&self.surface, device, queue, |render_pass| {
+            self.graphics.render(render_pass, device, queue);
+        });
+        match result {
+            Ok(_) => {}
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.surface.configure(device, &self.config);
+            }
+            Err(err) => log::error!("Failed to render settings window: {err}"),
+        }
     }
 }
 
+/// Everything needed to recreate the [State] of a suspended app.
+struct SuspendedGame {
+    game: minesweeper::Game,
+    /// How long the game had been running when it was suspended.
+    elapsed: std::time::Duration,
+    playback: Option<replay::Playback>,
+    keybindings: config::Keybindings,
+    /// Scale to open the window at, if any.
+    scale: Option<u32>,
+    skin: main_window_graphics::Skin,
+    skin_watcher: Option<main_window_graphics::SkinWatcher>,
+    /// Whether the game is only scaled by whole numbers.
+    integer_scaling: bool,
+}
+
 enum MinesweeperApp<'a> {
-    Suspended(Option<minesweeper::Game>),
-    Running(State<'a>),
+    Suspended(Option<Box<SuspendedGame>>),
+    Running(Box<State<'a>>),
 }
 
 impl<'a> ApplicationHandler for MinesweeperApp<'a> {
@@ -478,6 +1532,38 @@ impl<'a> ApplicationHandler for MinesweeperApp<'a> {
             MinesweeperApp::Running(state) => state,
         };
 
+        // Waking up to check the skin only goes on to update the timer or replay once they asked
+        // to be woken up too
+        let cause = match cause {
+            StartCause::ResumeTimeReached { start, .. } if state.skin_watcher.is_some() => {
+                state.reload_skin();
+                match state.resume_time {
+                    Some(requested_resume) if requested_resume <= std::time::Instant::now() => {
+                        StartCause::ResumeTimeReached {
+                            start,
+                            requested_resume,
+                        }
+                    }
+                    resume_time => {
+                        event_loop.set_control_flow(match resume_time {
+                            Some(time) => event_loop::ControlFlow::WaitUntil(time),
+                            None => event_loop::ControlFlow::Wait,
+                        });
+                        return;
+                    }
+                }
+            }
+            cause => cause,
+        };
+
+        // Replays schedule their own updates
+        if state.playback.is_some() {
+            if let StartCause::ResumeTimeReached { .. } = cause {
+                state.advance_playback(event_loop);
+            }
+            return;
+        }
+
         match cause {
             StartCause::Init => (),
             StartCause::ResumeTimeReached {
@@ -498,16 +1584,54 @@ impl<'a> ApplicationHandler for MinesweeperApp<'a> {
     fn resumed(&mut self, event_loop: &event_loop::ActiveEventLoop) {
         match self {
             MinesweeperApp::Running(..) => panic!("Minesweeper handler already running"),
-            MinesweeperApp::Suspended(game) => {
-                let game =
-                    std::mem::replace(game, None).expect("App suspended without storing game");
-                let window = Arc::new(
-                    event_loop
-                        .create_window(WindowAttributes::default())
-                        .unwrap(),
-                );
+            MinesweeperApp::Suspended(suspended_game) => {
+                let SuspendedGame {
+                    game,
+                    elapsed,
+                    playback,
+                    keybindings,
+                    scale,
+                    skin,
+                    skin_watcher,
+                    integer_scaling,
+                } = *std::mem::replace(suspended_game, None)
+                    .expect("App suspended without storing game");
+                let mut window_attributes = WindowAttributes::default();
+                if let Some(scale) = scale {
+                    let size = main_window_graphics::get_native_size(game.width, game.height);
+                    window_attributes = window_attributes.with_inner_size(
+                        winit::dpi::PhysicalSize::new(size.width * scale, size.height * scale),
+                    );
+                }
+                let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
                 window.set_title("Minesweeper");
-                std::mem::swap(self, &mut MinesweeperApp::Running(State::new(window, game)));
+                let mut state = match State::new(
+                    window,
+                    game,
+                    playback,
+                    keybindings,
+                    scale,
+                    skin,
+                    skin_watcher,
+                ) {
+                    Ok(state) => state,
+                    Err(err) => {
+                        println!("minesweeper: {err:#}");
+                        event_loop.exit();
+                        return;
+                    }
+                };
+                state
+                    .main_window_graphics
+                    .set_integer_scaling(integer_scaling);
+                state.main_window_graphics.rescale(&state.size);
+                if state.playback.is_some() {
+                    state.update_playback_title();
+                    state.finish_playback_update(event_loop);
+                } else {
+                    state.resume_timer(elapsed, event_loop);
+                }
+                std::mem::swap(self, &mut MinesweeperApp::Running(Box::new(state)));
             }
         }
     }
@@ -525,7 +1649,13 @@ impl<'a> ApplicationHandler for MinesweeperApp<'a> {
             MinesweeperApp::Running(state) => state,
         };
 
-        if window_id == state.window.id() {
+        if state
+            .settings_window
+            .as_ref()
+            .is_some_and(|settings_window| settings_window.window.id() == window_id)
+        {
+            state.settings_input(&event, event_loop);
+        } else if window_id == state.window.id() {
             if !state.input(&event, event_loop) {
                 match event {
                     WindowEvent::RedrawRequested => match state.render() {
@@ -556,7 +1686,10 @@ impl<'a> ApplicationHandler for MinesweeperApp<'a> {
                                 ..
                             },
                         ..
-                    } => event_loop.exit(),
+                    } => {
+                        state.save_game();
+                        event_loop.exit();
+                    }
                     _ => {}
                 }
             }
@@ -571,15 +1704,28 @@ impl<'a> ApplicationHandler for MinesweeperApp<'a> {
     ) {
     }
 
-    fn about_to_wait(&mut self, _event_loop: &event_loop::ActiveEventLoop) {}
+    fn about_to_wait(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        if let MinesweeperApp::Running(state) = self {
+            state.schedule_skin_check(event_loop);
+        }
+    }
 
     fn suspended(&mut self, event_loop: &event_loop::ActiveEventLoop) {
         let state = std::mem::replace(self, MinesweeperApp::Suspended(None));
         if let MinesweeperApp::Running(state) = state {
-            if let MinesweeperApp::Suspended(game) = self {
+            if let MinesweeperApp::Suspended(suspended_game) = self {
                 event_loop.set_control_flow(event_loop::ControlFlow::Wait);
-                std::mem::swap(game, &mut Some(state.game));
-                panic!("Not fully implemented: need to store game start time to be able to resume");
+                let elapsed = state.elapsed();
+                *suspended_game = Some(Box::new(SuspendedGame {
+                    skin: state.main_window_graphics.skin().clone(),
+                    game: state.game,
+                    elapsed,
+                    playback: state.playback,
+                    keybindings: state.keybindings,
+                    scale: state.scale,
+                    skin_watcher: state.skin_watcher,
+                    integer_scaling: state.main_window_graphics.integer_scaling(),
+                }));
             }
         }
     }
@@ -604,11 +1750,157 @@ pub async fn run() {
     }
 
     // Destructure starting params and start game
-    let (width, height, num_mines) = result.unwrap();
+    let starting_params::StartingParams {
+        width,
+        height,
+        num_mines,
+        seed,
+        no_guess,
+        practice,
+        scale,
+        integer_scaling,
+        question_marks,
+        keybindings,
+        replay,
+        resume,
+        board,
+        screenshot,
+        skin,
+        check_skin,
+        records,
+        stats,
+        json,
+    } = result.unwrap();
+
+    // Print the records instead of playing if asked
+    if records {
+        match records::Records::load() {
+            Ok(records) => println!("{}", records.table()),
+            Err(err) => println!("minesweeper: {err:#}"),
+        }
+        return;
+    }
+
+    // Print the stats instead of playing if asked
+    if stats {
+        match stats::Stats::load() {
+            Ok(stats) if json => println!("{}", stats.to_json()),
+            Ok(stats) => println!("{}", stats.table()),
+            Err(err) => println!("minesweeper: {err:#}"),
+        }
+        return;
+    }
+
+    // Print the problems with a skin instead of playing if asked
+    if let Some(dir) = check_skin {
+        let problems = main_window_graphics::Skin::check(&dir);
+        if problems.is_empty() {
+            println!("{} is a valid skin", dir.display());
+            return;
+        }
+        for problem in problems {
+            eprintln!("{}: {problem}", dir.display());
+        }
+        // Fail so scripts checking skins can tell an invalid one apart
+        std::process::exit(1);
+    }
+
+    // Play back the replay if one was given, continue a saved game if one was given or asked
+    // for, otherwise start a new game
+    let mut elapsed = std::time::Duration::ZERO;
+    let (game, playback) = if let Some(path) = replay {
+        let replay = match replay::Replay::load(&path) {
+            Ok(replay) => replay,
+            Err(err) => {
+                println!("minesweeper: {err:#}");
+                return;
+            }
+        };
+        let game = match replay.game() {
+            Ok(game) => game,
+            Err(err) => {
+                println!("minesweeper: invalid replay {}: {err:#}", path.display());
+                return;
+            }
+        };
+        (game, Some(replay::Playback::new(replay)))
+    } else if let Some(path) = board {
+        match save::load(&path) {
+            Ok((game, saved_elapsed)) => {
+                elapsed = saved_elapsed;
+                (game, None)
+            }
+            Err(err) => {
+                println!("minesweeper: {err:#}");
+                return;
+            }
+        }
+    } else if resume {
+        let loaded = save::default_path().and_then(|path| {
+            let loaded = save::load(&path)?;
+            // A game can only be resumed once
+            if let Err(err) = std::fs::remove_file(&path) {
+                log::warn!("Failed to remove saved game {}: {err}", path.display());
+            }
+            Ok(loaded)
+        });
+        match loaded {
+            Ok((game, saved_elapsed)) => {
+                elapsed = saved_elapsed;
+                (game, None)
+            }
+            Err(err) => {
+                println!("minesweeper: {err:#}");
+                return;
+            }
+        }
+    } else {
+        let mut game = minesweeper::Game::new(width, height, num_mines, seed);
+        game.no_guess = no_guess;
+        game.practice = practice;
+        game.question_marks = question_marks;
+        (game, None)
+    };
+
+    // Draw with the chosen skin or the built-in one, watching the chosen one for changes
+    let skin_watcher = skin.as_deref().map(main_window_graphics::SkinWatcher::new);
+    let skin = match skin {
+        Some(dir) => match main_window_graphics::Skin::load(&dir) {
+            Ok(skin) => skin,
+            Err(err) => {
+                println!("minesweeper: {err:#}");
+                return;
+            }
+        },
+        None => main_window_graphics::Skin::classic(),
+    };
+
+    // Render the game to a file instead of playing if asked
+    if let Some(path) = screenshot {
+        match screenshot::save(&game, elapsed, scale.unwrap_or(1), skin, &path) {
+            Ok(()) => println!("Screenshot saved to {}", path.display()),
+            Err(err) => {
+                eprintln!("minesweeper: {err:#}");
+                // Fail so scripts taking screenshots can tell nothing was saved
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     let event_loop = event_loop::EventLoop::new().unwrap();
     event_loop
-        .run_app(&mut MinesweeperApp::Suspended(Some(
-            minesweeper::Game::new(width, height, num_mines),
-        )))
+        .run_app(&mut MinesweeperApp::Suspended(Some(Box::new(
+            SuspendedGame {
+                game,
+                elapsed,
+                playback,
+                keybindings,
+                scale,
+                skin,
+                skin_watcher,
+                integer_scaling,
+            },
+        ))))
         .expect("Event loop crashed!");
 }
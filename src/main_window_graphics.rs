@@ -1,12 +1,29 @@
 use cgmath::num_traits::FromPrimitive;
-use std::sync::Arc;
+use std::{
+    ops::Range,
+    sync::Arc,
+};
 use wgpu::util::DeviceExt;
 
+mod offscreen;
+mod rasterizer;
+mod settings_window;
 mod seven_segment;
+mod skin;
 mod texture;
 
 use crate::minesweeper;
+pub use offscreen::OFFSCREEN_FORMAT;
+pub use settings_window::{
+    SettingsWindowGraphics,
+    get_settings_native_size,
+    settings_control_at,
+};
 pub use seven_segment::Display;
+pub use skin::{
+    Skin,
+    SkinWatcher,
+};
 
 /// Hard coded information about the number of pixels in the textures.
 pub const KNOWN_FRAME_WIDTHS: [u16; 2] = [12, 8];
@@ -16,19 +33,44 @@ pub const DISPLAY_OFFSET_X: u16 = DISPLAY_OFFSET_Y - 1;
 const DISPLAY_WIDTH: u16 = seven_segment::DIGIT_WIDTH * seven_segment::DIGITS_PER_DISPLAY as u16;
 const CELL_LENGTH: u16 = 16;
 const FACE_LENGTH: u16 = 24;
+/// Narrowest grid with room for both displays and the face between them.
+pub const MIN_GRID_WIDTH: minesweeper::Dim = (2 * (DISPLAY_OFFSET_X + DISPLAY_WIDTH) + FACE_LENGTH)
+    .div_ceil(CELL_LENGTH) as minesweeper::Dim;
 const FACE_OFFSET_Y: u16 = (KNOWN_FRAME_HEIGHTS[2] - FACE_LENGTH) / 2 + 1;
 const BORDER_INDEX_OFFSET: usize = 0;
 const DISPLAY_INDEX_OFFSET: usize = 15 + BORDER_INDEX_OFFSET;
 const FACE_INDEX_OFFSET: usize = 6 + DISPLAY_INDEX_OFFSET;
 const GRID_INDEX_OFFSET: usize = 1 + FACE_INDEX_OFFSET;
-const BORDER_ATLAS_OFFSET: [f32; 2] = [0.0, -93.0];
-const DISPLAY_ATLAS_OFFSET: [f32; 2] = [-64.0, 0.0];
-const FACE_ATLAS_OFFSET: [f32; 2] = [0.0, -69.0];
-const GRID_ATLAS_OFFSET: [f32; 2] = [0.0, 0.0];
+/// End of the rows of the border above the grid, which are part of the header.
+const HEADER_BORDER_END: usize = 9 + BORDER_INDEX_OFFSET;
+/// Ranges of the rectangles in the header, which stays put when the camera moves the board.
+const HEADER_RANGES: [Range<usize>; 2] = [
+    BORDER_INDEX_OFFSET..HEADER_BORDER_END,
+    DISPLAY_INDEX_OFFSET..GRID_INDEX_OFFSET,
+];
+/// Height in pixels of the header above the grid.
+const HEADER_HEIGHT: u16 = KNOWN_FRAME_HEIGHTS[1] + KNOWN_FRAME_HEIGHTS[2] + KNOWN_FRAME_HEIGHTS[3];
+/// Fewest cells across the longer side of the grid the camera can zoom in to.
+const MIN_ZOOMED_CELLS: f32 = 8.0;
+/// How many times larger each step of zooming in makes the board.
+const ZOOM_STEP: f32 = 1.25;
+/// Alpha of the tint drawn over cells by the probability overlay.
+const PROBABILITY_TINT_ALPHA: f32 = 0.5;
+/// Color drawn over the cell suggested by a hint.
+const HIGHLIGHT_COLOR: [f32; 4] = [0.0, 0.4, 1.0, 0.6];
+/// Color the timer is tinted with after setting a new record.
+const RECORD_COLOR: [f32; 4] = [1.0, 0.8, 0.0, 0.5];
+/// Size in pixels of the arms of the cross marking a replay's cursor.
+const CURSOR_MARKER_LENGTH: u16 = 9;
+const CURSOR_MARKER_THICKNESS: u16 = 3;
+const CURSOR_MARKER_COLOR: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
 
 /// Vertex indices for a square with the above vertices.
 const SQUARE_INDICES: &[u16] = &[0, 2, 1, 1, 2, 3];
 
+/// The texture atlas of the built-in skin.
+const ATLAS_BYTES: &[u8] = include_bytes!("main_window_graphics/atlas.png");
+
 /// [Vertex]s for a square.
 const SQUARE_VERTICES: &[texture::Vertex] = &[
     texture::Vertex {
@@ -49,77 +91,240 @@ const SQUARE_VERTICES: &[texture::Vertex] = &[
     },
 ];
 
+/// Buffer contents for drawing instances of a square, with no instances to start with.
+const SQUARE_BUFFERS: texture::BufferContents = texture::BufferContents {
+    indices: SQUARE_INDICES,
+    instance_data: &[],
+    vertices: SQUARE_VERTICES,
+};
+
 /// Handles all graphics for the main window.
 pub struct MainWindowGraphics {
-    texture_renderer: texture::TextureRenderer,
+    /// Draws the instances with the GPU, [None] if the graphics were created without one.
+    renderers: Option<Renderers>,
+    /// The atlas and where each sprite is in it.
+    skin: Skin,
     rectangles: texture::TextureInstances,
+    /// Name of the sprite each of the rectangles shows, so they can be found in another [Skin].
+    shown: Vec<&'static str>,
+    /// Tinted instances drawn on top of the board.
+    overlay: texture::TextureInstances,
+    /// Tinted instances drawn on top of the header.
+    header_overlay: texture::TextureInstances,
+    /// A marker at a cursor position drawn on top of the overlay.
+    cursor_marker: texture::TextureInstances,
+    /// Whether the cursor marker is over the header, so the camera doesn't move it.
+    cursor_in_header: bool,
     grid_width: minesweeper::Dim,
     grid_height: minesweeper::Dim,
     scaling: texture::Scaling,
+    /// Size of the window the graphics were last rescaled to, which the board is clipped to.
+    window_size: winit::dpi::PhysicalSize<u32>,
+}
+
+/// The parts of [MainWindowGraphics] that live on the GPU.
+struct Renderers {
+    texture_renderer: texture::TextureRenderer,
+    overlay_renderer: texture::TextureRenderer,
+    header_overlay_renderer: texture::TextureRenderer,
+    cursor_renderer: texture::TextureRenderer,
     scaling_buffer: wgpu::Buffer,
+    /// Scaling last written to the scaling buffer, if any.
+    uploaded_scaling: Option<texture::ScalingUniform>,
+    /// Scaling of the header, which the camera doesn't move.
+    scaling_bind_group: Arc<wgpu::BindGroup>,
+    camera_buffer: wgpu::Buffer,
+    /// Scaling through the camera last written to the camera buffer, if any.
+    uploaded_camera: Option<texture::ScalingUniform>,
+    /// Scaling of the board, zoomed and panned by the camera.
+    camera_bind_group: wgpu::BindGroup,
     // Fields used to indicate that resources are in use.
     // GPU will deallocate the corresponding resources when they are deallocated.
-    _scaling_bind_group: Arc<wgpu::BindGroup>,
     _render_pipeline: Arc<wgpu::RenderPipeline>,
 }
 
 impl MainWindowGraphics {
     /// Creates a new [MainWindowGraphics] displaying an unstarted minesweeper game with the given
-    /// parameters.
+    /// parameters, drawn with the given [Skin].
     pub fn new(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         texture_format: wgpu::TextureFormat,
         minesweeper_game: &minesweeper::Game,
+        skin: Skin,
     ) -> Self {
         let texture_layout = make_texture_layout(device);
         let (scaling, scaling_buffer, scaling_layout, scaling_bind_group) =
             make_scaling_items(device);
         let render_pipeline =
             make_render_pipeline(device, texture_format, &texture_layout, &scaling_layout);
+        let (camera_buffer, camera_bind_group) = make_scaling_buffer(
+            device,
+            &scaling_layout,
+            texture::ScalingUniform::through_camera(&scaling),
+            "Camera",
+        );
 
         let scaling_bind_group = Arc::new(scaling_bind_group);
         let render_pipeline = Arc::new(render_pipeline);
 
-        let diffuse_bytes = include_bytes!("atlas.png");
-        let texture =
-            texture::from_bytes(&device, &queue, diffuse_bytes, Some("Rectangles Texture"))
-                .expect("Failed to load Frame Texture");
+        let texture = texture::from_image(device, queue, skin.atlas(), Some("Rectangles Texture"));
         let texture_renderer = texture::TextureRenderer::new(
             device,
             render_pipeline.clone(),
             scaling_bind_group.clone(),
             &texture_layout,
             "Rectangles Texture".parse().unwrap(),
+            texture.clone(),
+            SQUARE_BUFFERS,
+        );
+        let overlay_renderer = texture::TextureRenderer::new(
+            device,
+            render_pipeline.clone(),
+            scaling_bind_group.clone(),
+            &texture_layout,
+            "Overlay Texture".parse().unwrap(),
+            texture.clone(),
+            SQUARE_BUFFERS,
+        );
+        let header_overlay_renderer = texture::TextureRenderer::new(
+            device,
+            render_pipeline.clone(),
+            scaling_bind_group.clone(),
+            &texture_layout,
+            "Header Overlay Texture".parse().unwrap(),
+            texture.clone(),
+            SQUARE_BUFFERS,
+        );
+        let cursor_renderer = texture::TextureRenderer::new(
+            device,
+            render_pipeline.clone(),
+            scaling_bind_group.clone(),
+            &texture_layout,
+            "Cursor Texture".parse().unwrap(),
             texture,
-            SQUARE_INDICES,
-            &[],
-            SQUARE_VERTICES,
+            SQUARE_BUFFERS,
         );
 
-        let mut result = Self {
+        let renderers = Renderers {
             texture_renderer,
+            overlay_renderer,
+            header_overlay_renderer,
+            cursor_renderer,
+            scaling_buffer,
+            uploaded_scaling: None,
+            scaling_bind_group,
+            camera_buffer,
+            uploaded_camera: None,
+            camera_bind_group,
+            _render_pipeline: render_pipeline,
+        };
+        Self::with_renderers(Some(renderers), scaling, minesweeper_game, skin)
+    }
+
+    /// Creates a new [MainWindowGraphics] like [MainWindowGraphics::new] that can only be drawn
+    /// on the CPU with [MainWindowGraphics::rasterize], for screenshots taken without a GPU.
+    pub fn without_gpu(minesweeper_game: &minesweeper::Game, skin: Skin) -> Self {
+        let scaling = texture::Scaling {
+            scaling: cgmath::Vector2::new(1.0, 1.0),
+            translation: cgmath::Vector2::new(0.0, 0.0),
+            integer: false,
+            zoom: 1.0,
+            pan: cgmath::Vector2::new(0.0, 0.0),
+        };
+        Self::with_renderers(None, scaling, minesweeper_game, skin)
+    }
+
+    /// Creates a new [MainWindowGraphics] drawn with the given renderers, if any.
+    fn with_renderers(
+        renderers: Option<Renderers>,
+        scaling: texture::Scaling,
+        minesweeper_game: &minesweeper::Game,
+        skin: Skin,
+    ) -> Self {
+        let mut result = Self {
+            renderers,
+            skin,
             rectangles: texture::TextureInstances::new(Vec::new()),
+            shown: Vec::new(),
+            overlay: texture::TextureInstances::new(Vec::new()),
+            header_overlay: texture::TextureInstances::new(Vec::new()),
+            cursor_marker: texture::TextureInstances::new(Vec::new()),
+            cursor_in_header: false,
             grid_width: minesweeper_game.width,
             grid_height: minesweeper_game.height,
             scaling,
-            scaling_buffer,
-            _scaling_bind_group: scaling_bind_group,
-            _render_pipeline: render_pipeline,
+            window_size: winit::dpi::PhysicalSize::new(0, 0),
         };
-        let rectangles = get_main_window_instances(&result, minesweeper_game);
+        let (rectangles, shown) = get_main_window_instances(&result, minesweeper_game)
+            .into_iter()
+            .unzip();
         result.rectangles.set_instances(rectangles);
+        result.shown = shown;
         result
     }
 
-    /// Returns the x component of the scaling array.
-    pub fn scaling_x(&self) -> f32 {
-        self.scaling.scaling.x
+    /// Returns the [Skin] the graphics are drawn with.
+    pub fn skin(&self) -> &Skin {
+        &self.skin
     }
 
-    /// Returns the y component of the scaling array.
-    pub fn scaling_y(&self) -> f32 {
-        self.scaling.scaling.y
+    /// Draws the graphics with the given [Skin] from now on, showing the same sprites as before
+    /// from its atlas.
+    pub fn set_skin(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, skin: Skin) {
+        if let Some(renderers) = &mut self.renderers {
+            let texture =
+                texture::from_image(device, queue, skin.atlas(), Some("Rectangles Texture"));
+            renderers
+                .texture_renderer
+                .set_texture(device, texture.clone());
+            renderers
+                .overlay_renderer
+                .set_texture(device, texture.clone());
+            renderers
+                .header_overlay_renderer
+                .set_texture(device, texture.clone());
+            renderers.cursor_renderer.set_texture(device, texture);
+        }
+        self.skin = skin;
+        for (idx, name) in self.shown.iter().enumerate() {
+            let sprite = self.skin.sprite(name);
+            self.rectangles.update_tex_instance(
+                idx,
+                self.get_tex_trans(sprite),
+                self.get_tex_scale(sprite),
+            );
+        }
+    }
+
+    /// Returns whether the game is only scaled by whole numbers.
+    pub fn integer_scaling(&self) -> bool {
+        self.scaling.integer
+    }
+
+    /// Sets whether the game is only scaled by whole numbers, leaving the rest of the window
+    /// empty. Takes effect the next time the graphics are rescaled.
+    pub fn set_integer_scaling(&mut self, integer_scaling: bool) {
+        self.scaling.integer = integer_scaling;
+    }
+
+    /// Converts a position in a window of the given size to a position relative to the game, with
+    /// both axes from -1 to 1 over the game, as used by [convert_to_over_grid]. Positions over the
+    /// board are where the camera shows them, as if it were zoomed all the way out.
+    pub fn window_to_game(
+        &self,
+        pos: &winit::dpi::PhysicalPosition<f64>,
+        size: &winit::dpi::PhysicalSize<u32>,
+    ) -> cgmath::Vector2<f32> {
+        let pos = self.scaling.window_to_game(pos, size);
+        let (bottom_left, top_right) = self.board_bounds();
+        if (bottom_left.x..=top_right.x).contains(&pos.x)
+            && (bottom_left.y..top_right.y).contains(&pos.y)
+        {
+            self.scaling.under_camera(pos)
+        } else {
+            pos
+        }
     }
 
     /// Updates the scaling array based on the new window size.
@@ -129,37 +334,311 @@ impl MainWindowGraphics {
             get_total_pixel_width(self.grid_width) as f32,
             get_total_pixel_height(self.grid_height) as f32,
         );
+        self.window_size = *size;
+    }
+
+    /// Zooms the camera in by the given number of steps, or out if negative, keeping what is under
+    /// the given position in a window of the given size in place.
+    pub fn zoom_at(
+        &mut self,
+        pos: &winit::dpi::PhysicalPosition<f64>,
+        size: &winit::dpi::PhysicalSize<u32>,
+        steps: f32,
+    ) {
+        let (bottom_left, top_right) = self.board_bounds();
+        let pos = self.scaling.window_to_game(pos, size);
+        let pos = cgmath::vec2(
+            pos.x.clamp(bottom_left.x, top_right.x),
+            pos.y.clamp(bottom_left.y, top_right.y),
+        );
+        let under_pos = self.scaling.under_camera(pos);
+        let max_zoom = (self.grid_width.max(self.grid_height) as f32 / MIN_ZOOMED_CELLS).max(1.0);
+        self.scaling.zoom = (self.scaling.zoom * ZOOM_STEP.powf(steps)).clamp(1.0, max_zoom);
+        self.scaling.pan = pos - under_pos * self.scaling.zoom;
+        self.clamp_camera();
+    }
+
+    /// Pans the camera so what was under the first position in a window of the given size is
+    /// under the second.
+    pub fn drag_camera(
+        &mut self,
+        from: &winit::dpi::PhysicalPosition<f64>,
+        to: &winit::dpi::PhysicalPosition<f64>,
+        size: &winit::dpi::PhysicalSize<u32>,
+    ) {
+        self.scaling.pan +=
+            self.scaling.window_to_game(to, size) - self.scaling.window_to_game(from, size);
+        self.clamp_camera();
+    }
+
+    /// Pans the camera to move the board right and up by the given number of cells, counted at
+    /// the size they are shown.
+    pub fn pan_camera(&mut self, cols: f32, rows: f32) {
+        let cell_width = 2.0 * CELL_LENGTH as f32 / get_total_pixel_width(self.grid_width) as f32;
+        let cell_height =
+            2.0 * CELL_LENGTH as f32 / get_total_pixel_height(self.grid_height) as f32;
+        self.scaling.pan.x += cols * cell_width * self.scaling.zoom;
+        self.scaling.pan.y += rows * cell_height * self.scaling.zoom;
+        self.clamp_camera();
+    }
+
+    /// Zooms the camera all the way out.
+    pub fn reset_camera(&mut self) {
+        self.scaling.zoom = 1.0;
+        self.scaling.pan = cgmath::vec2(0.0, 0.0);
+    }
+
+    /// Pans the camera back if the board no longer covers everything below the header.
+    fn clamp_camera(&mut self) {
+        let (bottom_left, top_right) = self.board_bounds();
+        let zoom = self.scaling.zoom;
+        // The board is shown from zoom * low + pan to zoom * high + pan
+        let clamp = |pan: f32, low: f32, high: f32| pan.clamp(high - zoom * high, low - zoom * low);
+        self.scaling.pan = cgmath::vec2(
+            clamp(self.scaling.pan.x, bottom_left.x, top_right.x),
+            clamp(self.scaling.pan.y, bottom_left.y, top_right.y),
+        );
+    }
+
+    /// Returns the bottom left and top right corners of the board, everything below the header,
+    /// relative to the game.
+    fn board_bounds(&self) -> (cgmath::Vector2<f32>, cgmath::Vector2<f32>) {
+        let header_height =
+            2.0 * HEADER_HEIGHT as f32 / get_total_pixel_height(self.grid_height) as f32;
+        (
+            cgmath::vec2(-1.0, -1.0),
+            cgmath::vec2(1.0, 1.0 - header_height),
+        )
     }
 
-    /// Renders the graphics to the given [wgpu::RenderPass].
+    /// Returns the columns and rows of the pixels the board is drawn in, clipping what the camera
+    /// moves out of it.
+    fn board_pixels(&self) -> [Range<u32>; 2] {
+        let (bottom_left, top_right) = self.board_bounds();
+        self.scaling
+            .covered_pixels(bottom_left, top_right, &self.window_size)
+    }
+
+    /// Returns the ranges of the rectangles on the board, which the camera moves.
+    fn board_ranges(&self) -> [Range<usize>; 2] {
+        [
+            HEADER_BORDER_END..DISPLAY_INDEX_OFFSET,
+            GRID_INDEX_OFFSET..self.rectangles.as_slice().len(),
+        ]
+    }
+
+    /// Regenerates the border, displays, face, and grid for the size of the given game and clears
+    /// everything drawn on top of them. Asks the window to become the new native size times the
+    /// whole number of times the old native size fit in it, so pixels keep their size.
+    /// Returns the new window size if the window was resized right away. Otherwise it is resized
+    /// later with a [winit::event::WindowEvent::Resized].
+    pub fn resize_grid(
+        &mut self,
+        minesweeper_game: &minesweeper::Game,
+        window: &winit::window::Window,
+    ) -> Option<winit::dpi::PhysicalSize<u32>> {
+        let size = window.inner_size();
+        let old_native_size = get_native_size(self.grid_width, self.grid_height);
+        let scale = (size.width / old_native_size.width)
+            .min(size.height / old_native_size.height)
+            .max(1);
+
+        self.grid_width = minesweeper_game.width;
+        self.grid_height = minesweeper_game.height;
+        let (rectangles, shown) = get_main_window_instances(self, minesweeper_game)
+            .into_iter()
+            .unzip();
+        self.rectangles.set_instances(rectangles);
+        self.shown = shown;
+        self.clear_overlay();
+        self.set_cursor_marker(None);
+        self.reset_camera();
+
+        let native_size = get_native_size(self.grid_width, self.grid_height);
+        let new_size = window.request_inner_size(winit::dpi::PhysicalSize::new(
+            native_size.width * scale,
+            native_size.height * scale,
+        ));
+        self.rescale(&new_size.unwrap_or(size));
+        new_size
+    }
+
+    /// Renders the graphics to the given [wgpu::RenderPass]. Draws nothing if the graphics were
+    /// created without a GPU.
     pub fn render(
         &mut self,
         render_pass: &mut wgpu::RenderPass,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
     ) {
-        queue.write_buffer(
-            &self.scaling_buffer,
-            0,
-            bytemuck::cast_slice(&[texture::ScalingUniform::new(&self.scaling)]),
-        );
-        self.texture_renderer
-            .prepare(self.rectangles.get_data(), device, queue);
-        self.texture_renderer.render(render_pass);
+        let [xs, ys] = self.board_pixels();
+        let board_ranges = self.board_ranges();
+        let Some(renderers) = &mut self.renderers else {
+            return;
+        };
+        // The scaling only changes when the window is resized, and the camera when it is moved
+        let uniforms = [
+            (
+                &renderers.scaling_buffer,
+                &mut renderers.uploaded_scaling,
+                texture::ScalingUniform::new(&self.scaling),
+            ),
+            (
+                &renderers.camera_buffer,
+                &mut renderers.uploaded_camera,
+                texture::ScalingUniform::through_camera(&self.scaling),
+            ),
+        ];
+        for (buffer, uploaded, uniform) in uniforms {
+            if *uploaded != Some(uniform) {
+                queue.write_buffer(buffer, 0, bytemuck::cast_slice(&[uniform]));
+                *uploaded = Some(uniform);
+            }
+        }
+        renderers
+            .texture_renderer
+            .prepare(&mut self.rectangles, device, queue);
+        renderers
+            .overlay_renderer
+            .prepare(&mut self.overlay, device, queue);
+        renderers
+            .header_overlay_renderer
+            .prepare(&mut self.header_overlay, device, queue);
+        renderers
+            .cursor_renderer
+            .prepare(&mut self.cursor_marker, device, queue);
+
+        // The board is drawn first and clipped to its part of the window, so the camera can't
+        // move it over the header or past the edges of the game
+        if !xs.is_empty() && !ys.is_empty() {
+            render_pass.set_scissor_rect(xs.start, ys.start, xs.len() as u32, ys.len() as u32);
+            let camera = &renderers.camera_bind_group;
+            for range in board_ranges {
+                renderers
+                    .texture_renderer
+                    .render_with(render_pass, camera, range);
+            }
+            renderers.overlay_renderer.render_with(
+                render_pass,
+                camera,
+                0..self.overlay.as_slice().len(),
+            );
+            if !self.cursor_in_header {
+                renderers.cursor_renderer.render_with(
+                    render_pass,
+                    camera,
+                    0..self.cursor_marker.as_slice().len(),
+                );
+            }
+            render_pass.set_scissor_rect(0, 0, self.window_size.width, self.window_size.height);
+        }
+        for range in HEADER_RANGES {
+            renderers.texture_renderer.render_with(
+                render_pass,
+                &renderers.scaling_bind_group,
+                range,
+            );
+        }
+        renderers.header_overlay_renderer.render(render_pass);
+        if self.cursor_in_header {
+            renderers.cursor_renderer.render(render_pass);
+        }
+    }
+
+    /// Draws a cross centered on the given position, in the same coordinates as the cursor
+    /// position passed to [convert_to_over_grid]. Removes the cross if given [None].
+    pub fn set_cursor_marker(&mut self, pos: Option<cgmath::Vector2<f32>>) {
+        let Some(pos) = pos else {
+            self.cursor_marker.set_instances(Vec::new());
+            return;
+        };
+        self.cursor_in_header = pos.y >= self.board_bounds().1.y;
+        // Size of a pixel relative to the game
+        let pixel_width = 2.0 / get_total_pixel_width(self.grid_width) as f32;
+        let pixel_height = 2.0 / get_total_pixel_height(self.grid_height) as f32;
+        let arm = |width: u16, height: u16| {
+            let mut instance = texture::Instance::new(
+                [
+                    pos.x - width as f32 * pixel_width / 2.0,
+                    pos.y - height as f32 * pixel_height / 2.0,
+                ],
+                [width as f32 * pixel_width, height as f32 * pixel_height],
+                [0.0, 0.0],
+                [0.0, 0.0],
+            );
+            instance.color = CURSOR_MARKER_COLOR;
+            instance
+        };
+        self.cursor_marker.set_instances(vec![
+            arm(CURSOR_MARKER_LENGTH, CURSOR_MARKER_THICKNESS),
+            arm(CURSOR_MARKER_THICKNESS, CURSOR_MARKER_LENGTH),
+        ]);
+    }
+
+    /// Tints every cell with a probability by how likely it is to be a mine, from green for safe
+    /// cells to red for certain mines. Replaces any previous overlay.
+    pub fn show_probabilities(&mut self, probabilities: &[Vec<Option<f64>>]) {
+        let mut overlay = Vec::new();
+        for (row, row_probabilities) in probabilities.iter().enumerate() {
+            for (col, probability) in row_probabilities.iter().enumerate() {
+                if let Some(probability) = probability {
+                    let probability = *probability as f32;
+                    overlay.push(self.tinted_cell_instance(
+                        (row as minesweeper::Row, col as minesweeper::Col),
+                        [probability, 1.0 - probability, 0.0, PROBABILITY_TINT_ALPHA],
+                    ));
+                }
+            }
+        }
+        self.overlay.set_instances(overlay);
+    }
+
+    /// Highlights the cell at the given [minesweeper::Pos] on top of the rest of the overlay.
+    pub fn highlight_cell(&mut self, pos: minesweeper::Pos) {
+        let instance = self.tinted_cell_instance(pos, HIGHLIGHT_COLOR);
+        self.overlay.get_instances().push(instance);
+    }
+
+    /// Tints the digits of the given [Display] on top of the rest of the header to celebrate a new
+    /// record.
+    pub fn highlight_display(&mut self, display: seven_segment::Display) {
+        let offset = match display {
+            Display::MinesUnflagged => 0,
+            Display::Timer => seven_segment::DIGITS_PER_DISPLAY,
+        };
+        for idx in 0..seven_segment::DIGITS_PER_DISPLAY {
+            let mut instance = *self
+                .rectangles
+                .get_instance(DISPLAY_INDEX_OFFSET + idx + offset);
+            instance.color = RECORD_COLOR;
+            self.header_overlay.get_instances().push(instance);
+        }
+    }
+
+    /// Removes everything drawn on top of the grid and the header.
+    pub fn clear_overlay(&mut self) {
+        self.overlay.set_instances(Vec::new());
+        self.header_overlay.set_instances(Vec::new());
+    }
+
+    /// Creates an instance covering the cell at the given [minesweeper::Pos] in the given color.
+    fn tinted_cell_instance(
+        &self,
+        (row, col): minesweeper::Pos,
+        color: [f32; 4],
+    ) -> texture::Instance {
+        let index = GRID_INDEX_OFFSET + (col as usize + row as usize * self.grid_width as usize);
+        let mut instance = *self.rectangles.get_instance(index);
+        instance.color = color;
+        instance
     }
 
     /// Resets all cells in the grid to be hidden.
     pub fn reset_grid(&mut self) {
         let num_cells = self.grid_width as usize * self.grid_height as usize;
         let grid_end_index = GRID_INDEX_OFFSET + num_cells;
-        let tex_coord_translation = self.get_tex_trans(
-            get_cell_tex_coords(&minesweeper::CellImage::Hidden),
-            GRID_ATLAS_OFFSET,
-        );
-        (GRID_INDEX_OFFSET..grid_end_index).for_each(|idx| {
-            self.rectangles
-                .update_tex_trans_instance(idx, tex_coord_translation)
-        });
+        let name = skin::cell_sprite(&minesweeper::CellImage::Hidden);
+        (GRID_INDEX_OFFSET..grid_end_index).for_each(|idx| self.show_sprite(idx, name));
     }
 
     /// Updates all cells as requested by `updates`.
@@ -167,10 +646,7 @@ impl MainWindowGraphics {
         updates.iter().for_each(|((row, col), cell_image)| {
             let index =
                 GRID_INDEX_OFFSET + (*col as usize + *row as usize * self.grid_width as usize);
-            let tex_coord_translation =
-                self.get_tex_trans(get_cell_tex_coords(&cell_image), GRID_ATLAS_OFFSET);
-            self.rectangles
-                .update_tex_trans_instance(index, tex_coord_translation);
+            self.show_sprite(index, skin::cell_sprite(cell_image));
         });
     }
 
@@ -180,41 +656,50 @@ impl MainWindowGraphics {
             Display::MinesUnflagged => false,
             Display::Timer => true,
         };
-        let updated_digits = seven_segment::get_texture_coords(val);
+        let updated_digits = seven_segment::get_images(val);
         let offset = if is_timer {
             seven_segment::DIGITS_PER_DISPLAY
         } else {
             0
         };
-        let updated_tex_coords = updated_digits
-            .into_iter()
-            .map(|data| self.get_tex_trans(data, DISPLAY_ATLAS_OFFSET))
-            .collect::<Vec<_>>()
-            .into_iter()
+        let updated_sprites = updated_digits
+            .iter()
+            .map(skin::digit_sprite)
             .zip(0..seven_segment::DIGITS_PER_DISPLAY);
-        for (data, idx) in updated_tex_coords {
-            self.rectangles
-                .update_tex_trans_instance(DISPLAY_INDEX_OFFSET + idx + offset, data);
+        for (name, idx) in updated_sprites {
+            self.show_sprite(DISPLAY_INDEX_OFFSET + idx + offset, name);
         }
     }
 
     /// Updates the [Face] with the given value.
     pub fn update_face(&mut self, face: Face) {
-        self.rectangles.update_tex_trans_instance(
-            FACE_INDEX_OFFSET,
-            self.get_tex_trans(get_face_tex_coords(&face), FACE_ATLAS_OFFSET),
-        );
+        self.show_sprite(FACE_INDEX_OFFSET, skin::face_sprite(&face));
     }
 
-    /// Creates a texture coordinate translation array using the given data and the data within this
-    /// [MainWindowGraphics].
-    fn get_tex_trans(&self, tex_translation: [u16; 2], offset: [f32; 2]) -> [f32; 2] {
-        let tex_coord_translation = [tex_translation[0] as f32, tex_translation[1] as f32];
-        let scaling = [
-            self.texture_renderer.atlas_width() as f32,
-            self.texture_renderer.atlas_height() as f32,
-        ];
-        Self::scale_data(tex_coord_translation, offset, scaling)
+    /// Shows the sprite with the given name in the rectangle with the given index.
+    fn show_sprite(&mut self, index: usize, name: &'static str) {
+        self.shown[index] = name;
+        let tex_coord_translation = self.get_tex_trans(self.skin.sprite(name));
+        self.rectangles
+            .update_tex_trans_instance(index, tex_coord_translation);
+    }
+
+    /// Creates a texture coordinate translation array for the given [skin::Sprite] using the data
+    /// within this [MainWindowGraphics].
+    fn get_tex_trans(&self, sprite: skin::Sprite) -> [f32; 2] {
+        let tex_coord_translation = [sprite.pos[0] as f32, sprite.pos[1] as f32];
+        let atlas = self.skin.atlas();
+        let scaling = [atlas.width() as f32, atlas.height() as f32];
+        Self::scale_data(tex_coord_translation, [0.0, 0.0], scaling)
+    }
+
+    /// Creates a texture coordinate scale array for the given [skin::Sprite] using the data within
+    /// this [MainWindowGraphics].
+    fn get_tex_scale(&self, sprite: skin::Sprite) -> [f32; 2] {
+        let tex_coord_scale = [sprite.size[0] as f32, sprite.size[1] as f32];
+        let atlas = self.skin.atlas();
+        let scaling = [atlas.width() as f32, atlas.height() as f32];
+        Self::scale_data(tex_coord_scale, [0.002, 0.002], scaling)
     }
 
     fn scale_data(data: [f32; 2], offset: [f32; 2], scaling: [f32; 2]) -> [f32; 2] {
@@ -224,64 +709,109 @@ impl MainWindowGraphics {
         ]
     }
 
-    /// Creates a [texture::Instance] using the given data and the data within this
-    /// [MainWindowGraphics].
+    /// Creates a [texture::Instance] of the given [skin::Sprite] using the given data and the data
+    /// within this [MainWindowGraphics].
     fn instance_from_pixel_data(
         &self,
         vertex_translation: [u16; 2],
         vertex_scale: [u16; 2],
-        tex_coord_translation: [u16; 2],
-        tex_coord_scale: [u16; 2],
-        offset: [f32; 2],
+        sprite: skin::Sprite,
     ) -> texture::Instance {
-        assert!(
-            tex_coord_translation[0] + tex_coord_scale[0] - 1 < self.texture_renderer.atlas_width()
-                && tex_coord_translation[1] + tex_coord_scale[1] - 1
-                    < self.texture_renderer.atlas_height(),
-            "Texture coordinates out of bounds"
-        );
-        let to_f32 = |array: [u16; 2]| [array[0] as f32, array[1] as f32];
-        let vertex_translation = to_f32(vertex_translation);
-        let vertex_scale = to_f32(vertex_scale);
-        let tex_coord_translation = to_f32(tex_coord_translation);
-        let tex_coord_scale = to_f32(tex_coord_scale);
-
-        let vertex_translation_offset = to_f32([
-            get_total_pixel_width(self.grid_width) / 2,
-            get_total_pixel_height(self.grid_height) / 2,
-        ]);
-        let vertex_scaling_offset = [0.0, 0.0];
-        let tex_coord_translation_offset = offset;
-        let tex_coord_scaling_offset = [0.002, 0.002];
-        let vertex_data_scaling = vertex_translation_offset;
-        let tex_coord_scaling = to_f32([
-            self.texture_renderer.atlas_width(),
-            self.texture_renderer.atlas_height(),
-        ]);
-
-        let vertex_translation = Self::scale_data(
+        let atlas = self.skin.atlas();
+        instance_from_pixels(
+            [
+                get_total_pixel_width(self.grid_width),
+                get_total_pixel_height(self.grid_height),
+            ],
+            [atlas.width() as u16, atlas.height() as u16],
             vertex_translation,
-            vertex_translation_offset,
-            vertex_data_scaling,
-        );
-        let vertex_scaling =
-            Self::scale_data(vertex_scale, vertex_scaling_offset, vertex_data_scaling);
-        let tex_coord_translation = Self::scale_data(
-            tex_coord_translation,
-            tex_coord_translation_offset,
-            tex_coord_scaling,
-        );
-        let tex_coord_scaling =
-            Self::scale_data(tex_coord_scale, tex_coord_scaling_offset, tex_coord_scaling);
-        texture::Instance::new(
-            vertex_translation,
-            vertex_scaling,
-            tex_coord_translation,
-            tex_coord_scaling,
+            vertex_scale,
+            sprite.pos,
+            sprite.size,
+        )
+    }
+
+    /// Creates a [texture::Instance] like [MainWindowGraphics::instance_from_pixel_data] of the
+    /// sprite with the given name, paired with the name.
+    fn named_instance(
+        &self,
+        vertex_translation: [u16; 2],
+        vertex_scale: [u16; 2],
+        name: &'static str,
+    ) -> (texture::Instance, &'static str) {
+        let sprite = self.skin.sprite(name);
+        (
+            self.instance_from_pixel_data(vertex_translation, vertex_scale, sprite),
+            name,
         )
     }
 }
 
+/// Creates a [texture::Instance] from pixel positions and sizes on an image of the given size,
+/// textured from an atlas of the given size.
+fn instance_from_pixels(
+    image_size: [u16; 2],
+    atlas_size: [u16; 2],
+    vertex_translation: [u16; 2],
+    vertex_scale: [u16; 2],
+    tex_coord_translation: [u16; 2],
+    tex_coord_scale: [u16; 2],
+) -> texture::Instance {
+    assert!(
+        tex_coord_translation[0] + tex_coord_scale[0] - 1 < atlas_size[0]
+            && tex_coord_translation[1] + tex_coord_scale[1] - 1 < atlas_size[1],
+        "Texture coordinates out of bounds"
+    );
+    let to_f32 = |array: [u16; 2]| [array[0] as f32, array[1] as f32];
+    let vertex_translation = to_f32(vertex_translation);
+    let vertex_scale = to_f32(vertex_scale);
+    let tex_coord_translation = to_f32(tex_coord_translation);
+    let tex_coord_scale = to_f32(tex_coord_scale);
+
+    let vertex_translation_offset = to_f32([image_size[0] / 2, image_size[1] / 2]);
+    let vertex_scaling_offset = [0.0, 0.0];
+    let tex_coord_translation_offset = [0.0, 0.0];
+    let tex_coord_scaling_offset = [0.002, 0.002];
+    let vertex_data_scaling = vertex_translation_offset;
+    let tex_coord_scaling = to_f32(atlas_size);
+
+    let vertex_translation = MainWindowGraphics::scale_data(
+        vertex_translation,
+        vertex_translation_offset,
+        vertex_data_scaling,
+    );
+    let vertex_scaling =
+        MainWindowGraphics::scale_data(vertex_scale, vertex_scaling_offset, vertex_data_scaling);
+    let tex_coord_translation = MainWindowGraphics::scale_data(
+        tex_coord_translation,
+        tex_coord_translation_offset,
+        tex_coord_scaling,
+    );
+    let tex_coord_scaling = MainWindowGraphics::scale_data(
+        tex_coord_scale,
+        tex_coord_scaling_offset,
+        tex_coord_scaling,
+    );
+    texture::Instance::new(
+        vertex_translation,
+        vertex_scaling,
+        tex_coord_translation,
+        tex_coord_scaling,
+    )
+}
+
+/// Returns the size of the minesweeper game in pixels at its native scale given the grid's width
+/// and height.
+pub fn get_native_size(
+    width: minesweeper::Dim,
+    height: minesweeper::Dim,
+) -> winit::dpi::PhysicalSize<u32> {
+    winit::dpi::PhysicalSize::new(
+        get_total_pixel_width(width) as u32,
+        get_total_pixel_height(height) as u32,
+    )
+}
+
 /// Returns the width of the minesweeper game in pixels given the grid's width.
 fn get_total_pixel_width(width: minesweeper::Dim) -> u16 {
     width as u16 * CELL_LENGTH + KNOWN_FRAME_WIDTHS.iter().sum::<u16>()
@@ -340,11 +870,11 @@ pub fn is_over_face(
     pos_x > left_bound && pos_x < right_bound && pos_y > lower_bound && pos_y < upper_bound
 }
 
-/// Creates the initial [texture::Instance]s.
+/// Creates the initial [texture::Instance]s, each with the name of the sprite it shows.
 fn get_main_window_instances(
     main_window_graphics: &MainWindowGraphics,
     minesweeper_game: &minesweeper::Game,
-) -> Vec<texture::Instance> {
+) -> Vec<(texture::Instance, &'static str)> {
     let grid_width = main_window_graphics.grid_width;
     let grid_height = main_window_graphics.grid_height;
     let mut instances =
@@ -371,48 +901,28 @@ fn get_main_window_instances(
         KNOWN_FRAME_HEIGHTS[2],
         KNOWN_FRAME_HEIGHTS[3],
     ];
-    let mut ttx = [0, KNOWN_FRAME_WIDTHS[0], 1];
-    let mut tty = [0, KNOWN_FRAME_HEIGHTS[3], 1, KNOWN_FRAME_HEIGHTS[1], 1];
-    let tsx = [KNOWN_FRAME_WIDTHS[0], 1, KNOWN_FRAME_WIDTHS[1]];
-    let tsy = [
-        KNOWN_FRAME_HEIGHTS[3],
-        1,
-        KNOWN_FRAME_HEIGHTS[1],
-        1,
-        KNOWN_FRAME_HEIGHTS[0],
-    ];
     for idx in 1..vtx.len() {
         vtx[idx] = vtx[idx - 1] + vtx[idx];
     }
     for idx in 1..vty.len() {
         vty[idx] = vty[idx - 1] + vty[idx];
     }
-    for idx in 1..ttx.len() {
-        ttx[idx] = ttx[idx - 1] + ttx[idx];
-    }
-    for idx in 1..tty.len() {
-        tty[idx] = tty[idx - 1] + tty[idx];
-    }
     vty.reverse();
     vsy.reverse();
-    for ((vty, vsy), (tty, tsy)) in vty.iter().zip(vsy.iter()).zip(tty.iter().zip(tsy.iter())) {
-        for ((vtx, vsx), (ttx, tsx)) in vtx.iter().zip(vsx.iter()).zip(ttx.iter().zip(tsx.iter())) {
-            instances.push(main_window_graphics.instance_from_pixel_data(
-                [*vtx, *vty],
-                [*vsx, *vsy],
-                [*ttx, *tty],
-                [*tsx, *tsy],
-                BORDER_ATLAS_OFFSET,
-            ));
+    // Rows are now from top to bottom like in the skin
+    let rows = vty.iter().zip(vsy.iter()).zip(skin::BORDER_SPRITES);
+    for ((vty, vsy), row) in rows {
+        let columns = vtx.iter().zip(vsx.iter()).zip(row);
+        for ((vtx, vsx), name) in columns {
+            instances.push(main_window_graphics.named_instance([*vtx, *vty], [*vsx, *vsy], name));
         }
     }
 
     // Create instance data for displays
-    let mines_left_digits = seven_segment::get_texture_coords(
-        (minesweeper_game.total_mines - minesweeper_game.flags) as i32,
-    )
-    .into_iter();
-    let timer_digits = seven_segment::get_texture_coords(0).into_iter();
+    let mines_left_digits =
+        seven_segment::get_images((minesweeper_game.total_mines - minesweeper_game.flags) as i32)
+            .into_iter();
+    let timer_digits = seven_segment::get_images(0).into_iter();
     let mut digits = mines_left_digits.chain(timer_digits);
     let vertex_scale = [seven_segment::DIGIT_WIDTH, seven_segment::DIGIT_HEIGHT];
     let y = KNOWN_FRAME_HEIGHTS[0]
@@ -425,18 +935,16 @@ fn get_main_window_instances(
     ];
     for left_side_x in left_side_xs.iter() {
         for digit in 0..seven_segment::DIGITS_PER_DISPLAY {
-            instances.push(main_window_graphics.instance_from_pixel_data(
+            instances.push(main_window_graphics.named_instance(
                 [left_side_x + seven_segment::DIGIT_WIDTH * digit as u16, y],
                 vertex_scale,
-                digits.next().unwrap(),
-                [13, 23],
-                DISPLAY_ATLAS_OFFSET,
+                skin::digit_sprite(&digits.next().unwrap()),
             ));
         }
     }
 
     // Create instance for face
-    instances.push(main_window_graphics.instance_from_pixel_data(
+    instances.push(main_window_graphics.named_instance(
         [
             KNOWN_FRAME_WIDTHS[0] + grid_width as u16 * CELL_LENGTH / 2 - FACE_LENGTH / 2,
             KNOWN_FRAME_HEIGHTS[0]
@@ -445,9 +953,7 @@ fn get_main_window_instances(
                 + FACE_OFFSET_Y,
         ],
         [FACE_LENGTH, FACE_LENGTH],
-        get_face_tex_coords(&Face::Neutral),
-        [FACE_LENGTH, FACE_LENGTH],
-        FACE_ATLAS_OFFSET,
+        skin::face_sprite(&Face::Neutral),
     ));
 
     // Create instance data for grid
@@ -459,15 +965,13 @@ fn get_main_window_instances(
                 (0..grid_width as u16)
                     .zip(row_images.into_iter())
                     .map(move |(col_idx, image)| {
-                        main_window_graphics.instance_from_pixel_data(
+                        main_window_graphics.named_instance(
                             [
                                 KNOWN_FRAME_WIDTHS[0] + col_idx * CELL_LENGTH,
                                 KNOWN_FRAME_HEIGHTS[0] + row_idx * CELL_LENGTH,
                             ],
                             [CELL_LENGTH, CELL_LENGTH],
-                            get_cell_tex_coords(&image),
-                            [CELL_LENGTH, CELL_LENGTH],
-                            GRID_ATLAS_OFFSET,
+                            skin::cell_sprite(&image),
                         )
                     })
             })
@@ -477,28 +981,6 @@ fn get_main_window_instances(
     instances
 }
 
-/// Returns the texture coordinates for the given [CellImage]. This is based on the texture atlas.
-fn get_cell_tex_coords(image: &minesweeper::CellImage) -> [u16; 2] {
-    use minesweeper::CellImage::*;
-    match image {
-        Zero => [0 * CELL_LENGTH, 0 * CELL_LENGTH],
-        One => [1 * CELL_LENGTH, 0 * CELL_LENGTH],
-        Two => [2 * CELL_LENGTH, 0 * CELL_LENGTH],
-        Three => [3 * CELL_LENGTH, 0 * CELL_LENGTH],
-        Four => [0 * CELL_LENGTH, 1 * CELL_LENGTH],
-        Five => [1 * CELL_LENGTH, 1 * CELL_LENGTH],
-        Six => [2 * CELL_LENGTH, 1 * CELL_LENGTH],
-        Seven => [3 * CELL_LENGTH, 1 * CELL_LENGTH],
-        Eight => [0 * CELL_LENGTH, 2 * CELL_LENGTH],
-        Mine => [1 * CELL_LENGTH, 2 * CELL_LENGTH],
-        WronglyFlagged => [2 * CELL_LENGTH, 2 * CELL_LENGTH],
-        SelectedMine => [3 * CELL_LENGTH, 2 * CELL_LENGTH],
-        Hidden => [0 * CELL_LENGTH, 3 * CELL_LENGTH],
-        Flagged => [1 * CELL_LENGTH, 3 * CELL_LENGTH],
-        QuestionMarked => [2 * CELL_LENGTH, 3 * CELL_LENGTH],
-    }
-}
-
 #[derive(PartialEq)]
 pub enum Face {
     Neutral,
@@ -508,18 +990,6 @@ pub enum Face {
     Pressed,
 }
 
-/// Returns the texture coordinates for the given [Face]. This is based on the texture atlas.
-fn get_face_tex_coords(image: &Face) -> [u16; 2] {
-    use Face::*;
-    match image {
-        Neutral => [0 * FACE_LENGTH, 0],
-        MouseDown => [1 * FACE_LENGTH, 0],
-        Victory => [2 * FACE_LENGTH, 0],
-        Loss => [3 * FACE_LENGTH, 0],
-        Pressed => [4 * FACE_LENGTH, 0],
-    }
-}
-
 /// Gets what the
 pub fn face_from_game_state(
     pressed: bool,
@@ -578,14 +1048,12 @@ fn make_scaling_items(
 ) {
     let scaling = texture::Scaling {
         scaling: cgmath::Vector2::new(1.0, 1.0),
+        translation: cgmath::Vector2::new(0.0, 0.0),
+        integer: false,
+        zoom: 1.0,
+        pan: cgmath::Vector2::new(0.0, 0.0),
     };
 
-    let scaling_uniform = texture::ScalingUniform::new(&scaling);
-    let scaling_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Scaling Buffer"),
-        contents: bytemuck::cast_slice(&[scaling_uniform]),
-        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-    });
     let scaling_bind_group_layout =
         device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[wgpu::BindGroupLayoutEntry {
@@ -600,15 +1068,12 @@ fn make_scaling_items(
             }],
             label: Some("Scaling Bind Group Layout"),
         });
-
-    let scaling_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        layout: &scaling_bind_group_layout,
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: scaling_buffer.as_entire_binding(),
-        }],
-        label: Some("Camera Binding Group"),
-    });
+    let (scaling_buffer, scaling_bind_group) = make_scaling_buffer(
+        device,
+        &scaling_bind_group_layout,
+        texture::ScalingUniform::new(&scaling),
+        "Scaling",
+    );
 
     (
         scaling,
@@ -618,6 +1083,30 @@ fn make_scaling_items(
     )
 }
 
+/// Creates a uniform buffer holding the given [texture::ScalingUniform] and a bind group with the
+/// given layout binding it, both labelled with the given name.
+fn make_scaling_buffer(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    scaling_uniform: texture::ScalingUniform,
+    name: &str,
+) -> (wgpu::Buffer, wgpu::BindGroup) {
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(&format!("{name} Buffer")),
+        contents: bytemuck::cast_slice(&[scaling_uniform]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: buffer.as_entire_binding(),
+        }],
+        label: Some(&format!("{name} Bind Group")),
+    });
+    (buffer, bind_group)
+}
+
 /// Creates the [wgpu::RenderPipeline] for rendering.
 /// Should be moved into texture.rs as it is the same for all [texture::TextureRenderer]s.
 fn make_render_pipeline(
@@ -652,7 +1141,7 @@ fn make_render_pipeline(
             entry_point: Some("fs_main"),
             targets: &[Some(wgpu::ColorTargetState {
                 format: texture_format,
-                blend: Some(wgpu::BlendState::REPLACE),
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
             compilation_options: wgpu::PipelineCompilationOptions::default(),
@@ -0,0 +1,140 @@
+use super::*;
+
+impl MainWindowGraphics {
+    /// Draws the graphics into an image of the given size on the CPU. Gives the same image as
+    /// [MainWindowGraphics::render_to_image] apart from tinted pixels, which can be one step off,
+    /// so it can stand in for the GPU when there is none and check the GPU's output when there is.
+    pub fn rasterize(&mut self, size: winit::dpi::PhysicalSize<u32>) -> image::RgbaImage {
+        self.rescale(&size);
+        // Cleared to black like the window
+        let mut image =
+            image::RgbaImage::from_pixel(size.width, size.height, image::Rgba([0, 0, 0, 255]));
+        // Drawn in the same order and clipped the same way as by the GPU, each with whether it is
+        // on the board
+        let rectangles = self.rectangles.as_slice();
+        let mut layers: Vec<(&[texture::Instance], bool)> = self
+            .board_ranges()
+            .into_iter()
+            .map(|range| (&rectangles[range], true))
+            .collect();
+        layers.push((self.overlay.as_slice(), true));
+        if !self.cursor_in_header {
+            layers.push((self.cursor_marker.as_slice(), true));
+        }
+        layers.extend(HEADER_RANGES.map(|range| (&rectangles[range], false)));
+        layers.push((self.header_overlay.as_slice(), false));
+        if self.cursor_in_header {
+            layers.push((self.cursor_marker.as_slice(), false));
+        }
+        let camera = self.scaling.through_camera();
+        let board = self.board_pixels();
+        let window = [0..size.width, 0..size.height];
+        for (instances, on_board) in layers {
+            let (scaling, clip) = if on_board {
+                (&camera, &board)
+            } else {
+                (&self.scaling, &window)
+            };
+            draw_instances(&mut image, self.skin.atlas(), instances, scaling, clip);
+        }
+        image
+    }
+}
+
+/// Draws the given instances on top of the given image on the CPU the same way the shader draws
+/// them on an sRGB surface, leaving out pixels outside of the given columns and rows like a
+/// scissor rectangle. Covers the pixels whose centers fall inside each instance, samples the
+/// atlas with nearest-neighbour filtering, and alpha blends in linear space. Textured pixels come
+/// out identical to the GPU's as long as edges don't land exactly on pixel centers, which sizes
+/// that are a multiple of the native size never do. Tinted pixels are only within one step of the
+/// GPU's since GPUs round the conversion of blended colors to sRGB differently, so they aren't
+/// pixel-identical.
+fn draw_instances(
+    image: &mut image::RgbaImage,
+    atlas: &image::RgbaImage,
+    instances: &[texture::Instance],
+    scaling: &texture::Scaling,
+    [clip_xs, clip_ys]: &[Range<u32>; 2],
+) {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    for instance in instances {
+        // Edges of the instance in pixels from the top left, like the GPU after the viewport
+        // transform
+        let to_x = |ndc: f32| (ndc * scaling.scaling.x + scaling.translation.x + 1.0) / 2.0 * width;
+        let to_y =
+            |ndc: f32| (1.0 - ndc * scaling.scaling.y - scaling.translation.y) / 2.0 * height;
+        let left = to_x(instance.vertex_translation[0]);
+        let right = to_x(instance.vertex_translation[0] + instance.vertex_scale[0]);
+        let top = to_y(instance.vertex_translation[1] + instance.vertex_scale[1]);
+        let bottom = to_y(instance.vertex_translation[1]);
+
+        // Pixels are covered if their center is inside, including centers on the top and left
+        // edges but not the bottom and right ones
+        let first_pixel = |edge: f32| (edge - 0.5).ceil().max(0.0) as u32;
+        let xs = first_pixel(left).max(clip_xs.start)..first_pixel(right).min(clip_xs.end);
+        let ys = first_pixel(top).max(clip_ys.start)..first_pixel(bottom).min(clip_ys.end);
+        for y in ys {
+            let v = instance.tex_coord_translation[1]
+                + (y as f32 + 0.5 - top) / (bottom - top) * instance.tex_coord_scale[1];
+            for x in xs.clone() {
+                let u = instance.tex_coord_translation[0]
+                    + (x as f32 + 0.5 - left) / (right - left) * instance.tex_coord_scale[0];
+                let color = if instance.color[3] > 0.0 {
+                    instance.color
+                } else {
+                    sample(atlas, u, v)
+                };
+                blend(image.get_pixel_mut(x, y), color);
+            }
+        }
+    }
+}
+
+/// Returns the linear color of the texel of the atlas at the given normalized texture
+/// coordinates, clamping to the edges like the sampler.
+fn sample(atlas: &image::RgbaImage, u: f32, v: f32) -> [f32; 4] {
+    let texel =
+        |coord: f32, size: u32| ((coord * size as f32).floor().max(0.0) as u32).min(size - 1);
+    let image::Rgba([r, g, b, a]) =
+        *atlas.get_pixel(texel(u, atlas.width()), texel(v, atlas.height()));
+    [
+        srgb_to_linear(r),
+        srgb_to_linear(g),
+        srgb_to_linear(b),
+        a as f32 / 255.0,
+    ]
+}
+
+/// Blends the given linear color over the given sRGB pixel like [wgpu::BlendState::ALPHA_BLENDING].
+fn blend(pixel: &mut image::Rgba<u8>, [r, g, b, a]: [f32; 4]) {
+    let image::Rgba([dst_r, dst_g, dst_b, dst_a]) = *pixel;
+    let mix = |src: f32, dst: u8| linear_to_srgb(src * a + srgb_to_linear(dst) * (1.0 - a));
+    let alpha = a + dst_a as f32 / 255.0 * (1.0 - a);
+    *pixel = image::Rgba([
+        mix(r, dst_r),
+        mix(g, dst_g),
+        mix(b, dst_b),
+        (alpha.clamp(0.0, 1.0) * 255.0).round() as u8,
+    ]);
+}
+
+/// Converts an sRGB encoded channel to linear.
+fn srgb_to_linear(channel: u8) -> f32 {
+    let channel = channel as f32 / 255.0;
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a linear channel to sRGB, rounding to the nearest encoded value.
+fn linear_to_srgb(channel: f32) -> u8 {
+    let channel = channel.clamp(0.0, 1.0);
+    let encoded = if channel <= 0.003_130_8 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round() as u8
+}
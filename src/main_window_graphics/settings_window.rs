@@ -0,0 +1,321 @@
+use super::*;
+use crate::settings::{
+    Control,
+    Field,
+    Settings,
+    Toggle,
+};
+
+/// Space in pixels around the edges of the settings window and between its rows of buttons.
+const MARGIN: u16 = 8;
+/// Space in pixels between rows of controls.
+const ROW_GAP: u16 = 4;
+/// Height in pixels of every row of controls. Rows are as tall as a seven-segment display.
+const ROW_HEIGHT: u16 = seven_segment::DIGIT_HEIGHT;
+/// Number of rows of fields and toggles.
+const NUM_ROWS: u16 = 7;
+/// Horizontal position in pixels of the fields and toggles, right of their icons.
+const INPUT_X: u16 = MARGIN + CELL_LENGTH + MARGIN;
+/// Size in pixels of the settings window at its native scale. Kept even so the center falls on a
+/// pixel boundary.
+const PANEL_WIDTH: u16 = (INPUT_X + DISPLAY_WIDTH + MARGIN).next_multiple_of(2);
+const PANEL_HEIGHT: u16 =
+    (MARGIN + NUM_ROWS * (ROW_HEIGHT + ROW_GAP) - ROW_GAP + MARGIN + FACE_LENGTH + MARGIN)
+        .next_multiple_of(2);
+/// Horizontal positions in pixels of the apply and cancel buttons.
+const BUTTON_XS: [u16; 2] = [
+    PANEL_WIDTH / 2 - MARGIN / 2 - FACE_LENGTH,
+    PANEL_WIDTH / 2 + MARGIN / 2,
+];
+/// Light gray of the classic frame. Colors are linear since the surface is sRGB.
+const BACKGROUND_COLOR: [f32; 4] = [0.527, 0.527, 0.527, 1.0];
+/// Color drawn over the control under the cursor.
+const HOVER_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.3];
+
+/// Handles all graphics for the settings window. Draws with the same atlas as the main window,
+/// using seven-segment displays for numbers and cells for icons and toggles.
+pub struct SettingsWindowGraphics {
+    skin: Skin,
+    texture_renderer: texture::TextureRenderer,
+    rectangles: texture::TextureInstances,
+    scaling: texture::Scaling,
+    scaling_buffer: wgpu::Buffer,
+    // Fields used to indicate that resources are in use.
+    // GPU will deallocate the corresponding resources when they are deallocated.
+    _scaling_bind_group: Arc<wgpu::BindGroup>,
+    _render_pipeline: Arc<wgpu::RenderPipeline>,
+}
+
+impl SettingsWindowGraphics {
+    /// Creates a new [SettingsWindowGraphics] showing the given settings, drawn with the given
+    /// [Skin].
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_format: wgpu::TextureFormat,
+        settings: &Settings,
+        skin: Skin,
+    ) -> Self {
+        let texture_layout = make_texture_layout(device);
+        let (scaling, scaling_buffer, scaling_layout, scaling_bind_group) =
+            make_scaling_items(device);
+        let render_pipeline =
+            make_render_pipeline(device, texture_format, &texture_layout, &scaling_layout);
+
+        let scaling_bind_group = Arc::new(scaling_bind_group);
+        let render_pipeline = Arc::new(render_pipeline);
+
+        let texture = texture::from_image(device, queue, skin.atlas(), Some("Settings Texture"));
+        let texture_renderer = texture::TextureRenderer::new(
+            device,
+            render_pipeline.clone(),
+            scaling_bind_group.clone(),
+            &texture_layout,
+            "Settings Texture".parse().unwrap(),
+            texture,
+            SQUARE_BUFFERS,
+        );
+
+        let mut result = Self {
+            skin,
+            texture_renderer,
+            rectangles: texture::TextureInstances::new(Vec::new()),
+            scaling,
+            scaling_buffer,
+            _scaling_bind_group: scaling_bind_group,
+            _render_pipeline: render_pipeline,
+        };
+        result.update(settings, None, None);
+        result
+    }
+
+    /// Draws with the given [Skin] from now on. The controls are redrawn with it on the next
+    /// [SettingsWindowGraphics::update].
+    pub fn set_skin(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, skin: Skin) {
+        let texture = texture::from_image(device, queue, skin.atlas(), Some("Settings Texture"));
+        self.texture_renderer.set_texture(device, texture);
+        self.skin = skin;
+    }
+
+    /// Sets whether the settings window is only scaled by whole numbers, leaving the rest of the
+    /// window empty. Takes effect the next time the graphics are rescaled.
+    pub fn set_integer_scaling(&mut self, integer_scaling: bool) {
+        self.scaling.integer = integer_scaling;
+    }
+
+    /// Converts a position in a window of the given size to a position relative to the settings
+    /// window's graphics, with both axes from -1 to 1, as used by [settings_control_at].
+    pub fn window_to_game(
+        &self,
+        pos: &winit::dpi::PhysicalPosition<f64>,
+        size: &winit::dpi::PhysicalSize<u32>,
+    ) -> cgmath::Vector2<f32> {
+        self.scaling.window_to_game(pos, size)
+    }
+
+    /// Updates the scaling array based on the new window size.
+    pub fn rescale(&mut self, size: &winit::dpi::PhysicalSize<u32>) {
+        self.scaling
+            .rescale(size, PANEL_WIDTH as f32, PANEL_HEIGHT as f32);
+    }
+
+    /// Redraws every control to match the given settings, tinting the hovered [Control] and
+    /// showing the pressed button as pressed.
+    pub fn update(
+        &mut self,
+        settings: &Settings,
+        hovered: Option<Control>,
+        pressed: Option<Control>,
+    ) {
+        let mut instances =
+            vec![self.colored_instance([0, 0], [PANEL_WIDTH, PANEL_HEIGHT], BACKGROUND_COLOR)];
+        for control in Control::ALL {
+            let ([x, y], _) = control_bounds(control);
+            match control {
+                Control::Field(field) => {
+                    let (icon, icon_size) = field_icon(field);
+                    instances.push(self.cell_instance(icon, [MARGIN, y], icon_size));
+                    let digits = seven_segment::get_images(settings.get(field) as i32);
+                    for (idx, digit) in digits.into_iter().enumerate() {
+                        instances.push(self.instance(
+                            [x + seven_segment::DIGIT_WIDTH * idx as u16, y],
+                            [seven_segment::DIGIT_WIDTH, seven_segment::DIGIT_HEIGHT],
+                            self.skin.digit(&digit),
+                        ));
+                    }
+                }
+                Control::Toggle(toggle) => {
+                    let cell_size = [CELL_LENGTH, CELL_LENGTH];
+                    instances.push(self.cell_instance(toggle_icon(toggle), [MARGIN, y], cell_size));
+                    let check_box = if settings.is_on(toggle) {
+                        minesweeper::CellImage::Flagged
+                    } else {
+                        minesweeper::CellImage::Hidden
+                    };
+                    instances.push(self.cell_instance(check_box, [x, y], cell_size));
+                }
+                Control::Apply | Control::Cancel => {
+                    let face = if pressed == Some(control) {
+                        Face::Pressed
+                    } else if control == Control::Apply {
+                        Face::Victory
+                    } else {
+                        Face::Loss
+                    };
+                    instances.push(self.instance(
+                        [x, y],
+                        [FACE_LENGTH, FACE_LENGTH],
+                        self.skin.face(&face),
+                    ));
+                }
+            }
+        }
+        if let Some(control) = hovered {
+            let (pos, size) = control_bounds(control);
+            instances.push(self.colored_instance(pos, size, HOVER_COLOR));
+        }
+        self.rectangles.set_instances(instances);
+    }
+
+    /// Renders the graphics to the given [wgpu::RenderPass].
+    pub fn render(
+        &mut self,
+        render_pass: &mut wgpu::RenderPass,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        queue.write_buffer(
+            &self.scaling_buffer,
+            0,
+            bytemuck::cast_slice(&[texture::ScalingUniform::new(&self.scaling)]),
+        );
+        self.texture_renderer
+            .prepare(&mut self.rectangles, device, queue);
+        self.texture_renderer.render(render_pass);
+    }
+
+    /// Creates an instance of the given [skin::Sprite], stretched to the given position and size in
+    /// pixels from the top left of the window.
+    fn instance(
+        &self,
+        [x, y]: [u16; 2],
+        size: [u16; 2],
+        sprite: skin::Sprite,
+    ) -> texture::Instance {
+        instance_from_pixels(
+            [PANEL_WIDTH, PANEL_HEIGHT],
+            [
+                self.texture_renderer.atlas_width(),
+                self.texture_renderer.atlas_height(),
+            ],
+            // Instances are positioned from the bottom left
+            [x, PANEL_HEIGHT - y - size[1]],
+            size,
+            sprite.pos,
+            sprite.size,
+        )
+    }
+
+    /// Creates an instance of the given [minesweeper::CellImage] stretched to the given size and
+    /// centered in the cell sized space of the row starting at the given position.
+    fn cell_instance(
+        &self,
+        image: minesweeper::CellImage,
+        [x, y]: [u16; 2],
+        size: [u16; 2],
+    ) -> texture::Instance {
+        self.instance(
+            [
+                x + (CELL_LENGTH - size[0]) / 2,
+                y + (ROW_HEIGHT - size[1]) / 2,
+            ],
+            size,
+            self.skin.cell(&image),
+        )
+    }
+
+    /// Creates an instance filled with the given color at the given position and size in pixels
+    /// from the top left of the window.
+    fn colored_instance(
+        &self,
+        pos: [u16; 2],
+        size: [u16; 2],
+        color: [f32; 4],
+    ) -> texture::Instance {
+        let corner = skin::Sprite {
+            pos: [0, 0],
+            size: [1, 1],
+        };
+        let mut instance = self.instance(pos, size, corner);
+        instance.color = color;
+        instance
+    }
+}
+
+/// Returns the size of the settings window in pixels at its native scale.
+pub fn get_settings_native_size() -> winit::dpi::PhysicalSize<u32> {
+    winit::dpi::PhysicalSize::new(PANEL_WIDTH as u32, PANEL_HEIGHT as u32)
+}
+
+/// Returns the [Control] under the given position in the settings window, if any.
+pub fn settings_control_at(pos: cgmath::Vector2<f32>) -> Option<Control> {
+    let x = (pos.x + 1.0) / 2.0 * PANEL_WIDTH as f32;
+    let y = (1.0 - pos.y) / 2.0 * PANEL_HEIGHT as f32;
+    Control::ALL.into_iter().find(|control| {
+        let ([left, top], [width, height]) = control_bounds(*control);
+        x >= left as f32
+            && x < (left + width) as f32
+            && y >= top as f32
+            && y < (top + height) as f32
+    })
+}
+
+/// Returns the position and size in pixels of the given [Control], measured from the top left of
+/// the window. Fields and toggles take up their whole row right of their icons.
+fn control_bounds(control: Control) -> ([u16; 2], [u16; 2]) {
+    let row_y = |row: u16| MARGIN + row * (ROW_HEIGHT + ROW_GAP);
+    let buttons_y = row_y(NUM_ROWS) - ROW_GAP + MARGIN;
+    match control {
+        Control::Field(field) => {
+            let row = match field {
+                Field::Width => 0,
+                Field::Height => 1,
+                Field::Mines => 2,
+                Field::PercentMines => 3,
+            };
+            ([INPUT_X, row_y(row)], [DISPLAY_WIDTH, ROW_HEIGHT])
+        }
+        Control::Toggle(toggle) => {
+            let row = match toggle {
+                Toggle::QuestionMarks => 4,
+                Toggle::NoGuess => 5,
+                Toggle::Practice => 6,
+            };
+            ([INPUT_X, row_y(row)], [CELL_LENGTH, ROW_HEIGHT])
+        }
+        Control::Apply => ([BUTTON_XS[0], buttons_y], [FACE_LENGTH, FACE_LENGTH]),
+        Control::Cancel => ([BUTTON_XS[1], buttons_y], [FACE_LENGTH, FACE_LENGTH]),
+    }
+}
+
+/// Returns the cell shown as the icon of the given [Field] and the size it is stretched to. Width
+/// and height are shown as a hidden cell stretched in their direction.
+fn field_icon(field: Field) -> (minesweeper::CellImage, [u16; 2]) {
+    use crate::minesweeper::CellImage;
+    match field {
+        Field::Width => (CellImage::Hidden, [CELL_LENGTH, CELL_LENGTH / 2]),
+        Field::Height => (CellImage::Hidden, [CELL_LENGTH / 2, CELL_LENGTH]),
+        Field::Mines => (CellImage::Mine, [CELL_LENGTH, CELL_LENGTH]),
+        Field::PercentMines => (CellImage::SelectedMine, [CELL_LENGTH, CELL_LENGTH]),
+    }
+}
+
+/// Returns the cell shown as the icon of the given [Toggle].
+fn toggle_icon(toggle: Toggle) -> minesweeper::CellImage {
+    use crate::minesweeper::CellImage;
+    match toggle {
+        Toggle::QuestionMarks => CellImage::QuestionMarked,
+        Toggle::NoGuess => CellImage::One,
+        Toggle::Practice => CellImage::WronglyFlagged,
+    }
+}
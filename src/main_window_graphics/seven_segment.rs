@@ -16,7 +16,7 @@ pub enum Display {
 /// A seven-segment display can, of course, display more than these, but this is all that's needed
 /// for minesweeper.
 #[derive(PartialEq, Debug)]
-enum Image {
+pub(crate) enum Image {
     Blank,
     Zero,
     One,
@@ -51,29 +51,10 @@ impl Image {
             _ => panic!("Invalid number: {}", value),
         }
     }
-
-    /// Gives the texture coordinates
-    fn get_tex_coords(image: &Image) -> [u16; 2] {
-        use Image::*;
-        match image {
-            Zero => [0 * DIGIT_WIDTH, 0 * DIGIT_HEIGHT],
-            One => [1 * DIGIT_WIDTH, 0 * DIGIT_HEIGHT],
-            Two => [2 * DIGIT_WIDTH, 0 * DIGIT_HEIGHT],
-            Three => [3 * DIGIT_WIDTH, 0 * DIGIT_HEIGHT],
-            Four => [0 * DIGIT_WIDTH, 1 * DIGIT_HEIGHT],
-            Five => [1 * DIGIT_WIDTH, 1 * DIGIT_HEIGHT],
-            Six => [2 * DIGIT_WIDTH, 1 * DIGIT_HEIGHT],
-            Seven => [3 * DIGIT_WIDTH, 1 * DIGIT_HEIGHT],
-            Eight => [0 * DIGIT_WIDTH, 2 * DIGIT_HEIGHT],
-            Nine => [1 * DIGIT_WIDTH, 2 * DIGIT_HEIGHT],
-            Blank => [2 * DIGIT_WIDTH, 2 * DIGIT_HEIGHT],
-            Negative => [3 * DIGIT_WIDTH, 2 * DIGIT_HEIGHT],
-        }
-    }
 }
 
 /// Gives the [Image]s to be displayed for the given value.
-fn get_images(val: i32) -> [Image; 3] {
+pub(crate) fn get_images(val: i32) -> [Image; 3] {
     use Image::*;
     if val >= 999 {
         [Nine, Nine, Nine]
@@ -104,15 +85,3 @@ fn get_images(val: i32) -> [Image; 3] {
         [result_100s, result_10s, result_1s]
     }
 }
-
-/// Gives the texture coordinates to be used for rendering the given value.
-pub fn get_texture_coords(val: i32) -> [[u16; 2]; 3] {
-    let mut result = get_images(val)
-        .into_iter()
-        .map(|image| Image::get_tex_coords(&image));
-    [
-        result.next().unwrap(),
-        result.next().unwrap(),
-        result.next().unwrap(),
-    ]
-}
@@ -0,0 +1,442 @@
+use super::*;
+use anyhow::{
+    Context,
+    Result,
+    anyhow,
+    bail,
+    ensure,
+};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        Duration,
+        Instant,
+        SystemTime,
+    },
+};
+
+/// Name of the manifest file inside a skin's directory.
+const MANIFEST_FILE_NAME: &str = "skin.txt";
+/// Manifest of the built-in skin, whose atlas is embedded in the executable.
+const CLASSIC_MANIFEST: &str = include_str!("skin.txt");
+/// Names of the sprites of every [crate::minesweeper::CellImage].
+const CELL_SPRITES: [&str; 15] = [
+    "cell.zero",
+    "cell.one",
+    "cell.two",
+    "cell.three",
+    "cell.four",
+    "cell.five",
+    "cell.six",
+    "cell.seven",
+    "cell.eight",
+    "cell.mine",
+    "cell.wrongly_flagged",
+    "cell.selected_mine",
+    "cell.hidden",
+    "cell.flagged",
+    "cell.question_marked",
+];
+/// Names of the sprites of every [Face].
+const FACE_SPRITES: [&str; 5] = [
+    "face.neutral",
+    "face.mouse_down",
+    "face.victory",
+    "face.loss",
+    "face.pressed",
+];
+/// Names of the sprites of every digit of the seven-segment displays.
+const DIGIT_SPRITES: [&str; 12] = [
+    "digit.0",
+    "digit.1",
+    "digit.2",
+    "digit.3",
+    "digit.4",
+    "digit.5",
+    "digit.6",
+    "digit.7",
+    "digit.8",
+    "digit.9",
+    "digit.blank",
+    "digit.negative",
+];
+/// Names of the sprites of the border by row from top to bottom, named by what each row is next
+/// to, and by column from left to right.
+pub(super) const BORDER_SPRITES: [[&str; 3]; 5] = [
+    ["border.top.left", "border.top.center", "border.top.right"],
+    [
+        "border.header.left",
+        "border.header.center",
+        "border.header.right",
+    ],
+    [
+        "border.middle.left",
+        "border.middle.center",
+        "border.middle.right",
+    ],
+    [
+        "border.grid.left",
+        "border.grid.center",
+        "border.grid.right",
+    ],
+    [
+        "border.bottom.left",
+        "border.bottom.center",
+        "border.bottom.right",
+    ],
+];
+/// How often the files of a watched skin are checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A rectangle of the atlas holding one sprite, in pixels from the atlas' top left.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    pub pos: [u16; 2],
+    pub size: [u16; 2],
+}
+
+/// How the game looks: an atlas and where each sprite is in it. Sprites are stretched to the size
+/// they are drawn at, but only the position of a cell, face, or digit changes when it is updated,
+/// so all the sprites of each of those kinds are the same size.
+#[derive(Clone)]
+pub struct Skin {
+    atlas: image::RgbaImage,
+    sprites: HashMap<String, Sprite>,
+}
+
+impl Skin {
+    /// Returns the built-in skin.
+    pub fn classic() -> Self {
+        let atlas = image::load_from_memory(ATLAS_BYTES)
+            .expect("Failed to load atlas")
+            .to_rgba8();
+        let (_, sprites, mut problems) = parse_manifest(CLASSIC_MANIFEST);
+        problems.extend(check_sprites(&sprites, Some(atlas.dimensions())));
+        assert!(
+            problems.is_empty(),
+            "Built-in skin is invalid: {problems:?}"
+        );
+        Self { atlas, sprites }
+    }
+
+    /// Loads the skin in the given directory, made of a `skin.txt` manifest and the atlas it
+    /// names.
+    /// Returns an error listing every problem if either can't be read or if the manifest is invalid
+    /// or doesn't fit the atlas.
+    pub fn load(dir: &Path) -> Result<Self> {
+        Self::read(dir)
+            .map_err(|problems| anyhow!(problems.join("\n")))
+            .with_context(|| format!("Invalid skin {}", dir.display()))
+    }
+
+    /// Returns every problem with the skin in the given directory, which is empty if it can be
+    /// loaded.
+    pub fn check(dir: &Path) -> Vec<String> {
+        Self::read(dir).err().unwrap_or_default()
+    }
+
+    /// Reads the skin in the given directory, checking as much of it as can be read.
+    /// Returns every problem found if it is invalid.
+    fn read(dir: &Path) -> Result<Self, Vec<String>> {
+        let manifest_path = dir.join(MANIFEST_FILE_NAME);
+        let manifest = fs::read_to_string(&manifest_path)
+            .map_err(|err| vec![format!("could not read {}: {err}", manifest_path.display())])?;
+        let (atlas_name, sprites, mut problems) = parse_manifest(&manifest);
+        let atlas = atlas_name.and_then(|name| {
+            let atlas_path = dir.join(name);
+            match image::open(&atlas_path) {
+                Ok(atlas) => Some(atlas.to_rgba8()),
+                Err(err) => {
+                    problems.push(format!(
+                        "could not load atlas {}: {err}",
+                        atlas_path.display()
+                    ));
+                    None
+                }
+            }
+        });
+        // Without an atlas, everything but the bounds of the sprites can still be checked
+        problems.extend(check_sprites(
+            &sprites,
+            atlas.as_ref().map(|atlas| atlas.dimensions()),
+        ));
+        match atlas {
+            Some(atlas) if problems.is_empty() => Ok(Self { atlas, sprites }),
+            _ => Err(problems),
+        }
+    }
+
+    /// Returns the atlas every sprite is in.
+    pub fn atlas(&self) -> &image::RgbaImage {
+        &self.atlas
+    }
+
+    /// Returns the sprite of the given [crate::minesweeper::CellImage].
+    pub(super) fn cell(&self, image: &minesweeper::CellImage) -> Sprite {
+        self.sprite(cell_sprite(image))
+    }
+
+    /// Returns the sprite of the given [Face].
+    pub(super) fn face(&self, face: &Face) -> Sprite {
+        self.sprite(face_sprite(face))
+    }
+
+    /// Returns the sprite of the given digit of a seven-segment display.
+    pub(super) fn digit(&self, digit: &seven_segment::Image) -> Sprite {
+        self.sprite(digit_sprite(digit))
+    }
+
+    /// Returns the sprite with the given name, which was checked to exist when loading.
+    pub(super) fn sprite(&self, name: &str) -> Sprite {
+        self.sprites[name]
+    }
+}
+
+/// Watches the files of a skin loaded from a directory by when they were last modified, so the
+/// skin can be reloaded while the game is running.
+pub struct SkinWatcher {
+    dir: PathBuf,
+    /// The manifest and the atlas it names, with when each was last modified if it could be read.
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    next_check: Instant,
+}
+
+impl SkinWatcher {
+    /// Starts watching the skin in the given directory as it is now.
+    pub fn new(dir: &Path) -> Self {
+        let mut watcher = Self {
+            dir: dir.to_path_buf(),
+            files: Vec::new(),
+            next_check: Instant::now() + WATCH_INTERVAL,
+        };
+        watcher.files = watcher.current_files();
+        watcher
+    }
+
+    /// Returns when the files of the skin are due to be checked.
+    pub fn next_check(&self) -> Instant {
+        self.next_check
+    }
+
+    /// Checks whether the files of the skin changed since they were last checked, if they are due
+    /// to be checked.
+    /// Returns the reloaded skin if they did, or an error if it can't be loaded anymore, in which
+    /// case it is tried again once they change again.
+    pub fn check(&mut self) -> Option<Result<Skin>> {
+        let now = Instant::now();
+        if now < self.next_check {
+            return None;
+        }
+        self.next_check = now + WATCH_INTERVAL;
+        let files = self.current_files();
+        if files == self.files {
+            return None;
+        }
+        self.files = files;
+        Some(Skin::load(&self.dir))
+    }
+
+    /// Returns the files of the skin with when each was last modified. The atlas is left out if
+    /// the manifest can't be read or doesn't name one.
+    fn current_files(&self) -> Vec<(PathBuf, Option<SystemTime>)> {
+        let manifest_path = self.dir.join(MANIFEST_FILE_NAME);
+        let atlas_path = fs::read_to_string(&manifest_path)
+            .ok()
+            .and_then(|manifest| parse_manifest(&manifest).0)
+            .map(|atlas_name| self.dir.join(atlas_name));
+        std::iter::once(manifest_path)
+            .chain(atlas_path)
+            .map(|path| {
+                let modified = fs::metadata(&path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok();
+                (path, modified)
+            })
+            .collect()
+    }
+}
+
+/// Returns the name of the sprite of the given [crate::minesweeper::CellImage].
+pub(super) fn cell_sprite(image: &minesweeper::CellImage) -> &'static str {
+    use crate::minesweeper::CellImage::*;
+    let index = match image {
+        Zero => 0,
+        One => 1,
+        Two => 2,
+        Three => 3,
+        Four => 4,
+        Five => 5,
+        Six => 6,
+        Seven => 7,
+        Eight => 8,
+        Mine => 9,
+        WronglyFlagged => 10,
+        SelectedMine => 11,
+        Hidden => 12,
+        Flagged => 13,
+        QuestionMarked => 14,
+    };
+    CELL_SPRITES[index]
+}
+
+/// Returns the name of the sprite of the given [Face].
+pub(super) fn face_sprite(face: &Face) -> &'static str {
+    let index = match face {
+        Face::Neutral => 0,
+        Face::MouseDown => 1,
+        Face::Victory => 2,
+        Face::Loss => 3,
+        Face::Pressed => 4,
+    };
+    FACE_SPRITES[index]
+}
+
+/// Returns the name of the sprite of the given digit of a seven-segment display.
+pub(super) fn digit_sprite(digit: &seven_segment::Image) -> &'static str {
+    use seven_segment::Image::*;
+    let index = match digit {
+        Zero => 0,
+        One => 1,
+        Two => 2,
+        Three => 3,
+        Four => 4,
+        Five => 5,
+        Six => 6,
+        Seven => 7,
+        Eight => 8,
+        Nine => 9,
+        Blank => 10,
+        Negative => 11,
+    };
+    DIGIT_SPRITES[index]
+}
+
+/// The kinds of sprites in a skin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SpriteKind {
+    Cell,
+    Face,
+    Digit,
+    Border,
+}
+
+/// Returns the names of every sprite a skin needs, grouped by kind.
+fn sprite_sets() -> [(SpriteKind, Vec<&'static str>); 4] {
+    [
+        (SpriteKind::Cell, CELL_SPRITES.to_vec()),
+        (SpriteKind::Face, FACE_SPRITES.to_vec()),
+        (SpriteKind::Digit, DIGIT_SPRITES.to_vec()),
+        (SpriteKind::Border, BORDER_SPRITES.concat()),
+    ]
+}
+
+/// Returns a message for every sprite that is missing, empty, or outside of an atlas of the given
+/// width and height, and for every cell, face, or digit whose size differs from the first of its
+/// kind. Bounds are only checked if the size of the atlas is given.
+fn check_sprites(sprites: &HashMap<String, Sprite>, atlas_size: Option<(u32, u32)>) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (kind, set) in sprite_sets() {
+        let mut first: Option<(&str, [u16; 2])> = None;
+        for name in set {
+            let Some(sprite) = sprites.get(name) else {
+                problems.push(format!("missing {name}"));
+                continue;
+            };
+            let [x, y] = sprite.pos;
+            let [width, height] = sprite.size;
+            if width == 0 || height == 0 {
+                problems.push(format!("{name} is empty: {width}x{height}"));
+            }
+            if let Some((atlas_width, atlas_height)) = atlas_size {
+                let [right, bottom] = [x as u32 + width as u32, y as u32 + height as u32];
+                if right > atlas_width || bottom > atlas_height {
+                    problems.push(format!(
+                        "{name} at {x}, {y} with size {width}x{height} reaches {right}, {bottom}, \
+                        outside of the {atlas_width}x{atlas_height} atlas"
+                    ));
+                }
+            }
+            // The border never changes, so its pieces are the only sprites free in size
+            if kind == SpriteKind::Border {
+                continue;
+            }
+            match first {
+                None => first = Some((name, sprite.size)),
+                Some((first, [first_width, first_height])) => {
+                    if sprite.size != [first_width, first_height] {
+                        problems.push(format!(
+                            "{name} is {width}x{height} while {first} is \
+                            {first_width}x{first_height}"
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// Parses the contents of a skin manifest, returning the file name of the atlas if it is given, the
+/// sprites, and a message for every problem.
+/// Every line is a `key = value` pair, with empty lines and lines starting with `#` ignored. The
+/// atlas is given by `atlas` and every sprite by its x, y, width, and height in the atlas. Messages
+/// for invalid lines name the line, which is otherwise skipped.
+fn parse_manifest(contents: &str) -> (Option<String>, HashMap<String, Sprite>, Vec<String>) {
+    let mut atlas = None;
+    let mut sprites = HashMap::new();
+    let mut problems = Vec::new();
+    for (idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parse = || -> Result<()> {
+            let Some((key, value)) = line.split_once('=') else {
+                bail!("expected key = value: {line}");
+            };
+            let (key, value) = (key.trim(), value.trim());
+            if key == "atlas" {
+                ensure!(atlas.is_none(), "atlas is given twice");
+                atlas = Some(value.to_string());
+                return Ok(());
+            }
+            ensure!(
+                sprite_sets().iter().any(|(_, set)| set.contains(&key)),
+                "unknown key: {key}"
+            );
+            let numbers = value
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<u16>, _>>()
+                .map_err(|_| anyhow!("invalid sprite for {key}: {value}"))?;
+            let [x, y, width, height] = numbers[..] else {
+                bail!("expected x, y, width, and height for {key}: {value}");
+            };
+            ensure!(
+                sprites
+                    .insert(
+                        key.to_string(),
+                        Sprite {
+                            pos: [x, y],
+                            size: [width, height],
+                        },
+                    )
+                    .is_none(),
+                "{key} is given twice"
+            );
+            Ok(())
+        };
+        if let Err(err) = parse() {
+            problems.push(format!("line {}: {err}", idx + 1));
+        }
+    }
+    if atlas.is_none() {
+        problems.push("missing atlas".to_string());
+    }
+    (atlas, sprites, problems)
+}
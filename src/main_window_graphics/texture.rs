@@ -1,11 +1,23 @@
-use anyhow::*;
-use image::GenericImageView;
-use std::sync::Arc;
+use std::{
+    ops::Range,
+    sync::Arc,
+};
 use wgpu::util::DeviceExt;
 
 /// Stores info on how to scale each instance to fit the window as an x-scaling and a y-scaling.
+#[derive(Clone, Copy)]
 pub struct Scaling {
     pub scaling: cgmath::Vector2<f32>,
+    /// Where the center of the game is in the window, with both axes from -1 to 1. Only off
+    /// center when scaling by whole numbers, to line the pixels up with the window's.
+    pub translation: cgmath::Vector2<f32>,
+    /// Whether the game is only scaled by whole numbers, leaving the rest of the window empty.
+    pub integer: bool,
+    /// How many times larger the camera shows what it looks at, at least 1.
+    pub zoom: f32,
+    /// Where the camera moves what it looks at after zooming in, with both axes from -1 to 1 over
+    /// the game.
+    pub pan: cgmath::Vector2<f32>,
 }
 
 impl Scaling {
@@ -17,12 +29,18 @@ impl Scaling {
     ) -> Self {
         let mut result = Self {
             scaling: cgmath::Vector2::new(0.0, 0.0),
+            translation: cgmath::Vector2::new(0.0, 0.0),
+            integer: false,
+            zoom: 1.0,
+            pan: cgmath::Vector2::new(0.0, 0.0),
         };
         result.rescale(win_size, aspect_ratio_height, aspect_ratio_width);
         result
     }
 
-    /// Updates the camera based on the given window size and game aspect ratio.
+    /// Updates the camera based on the given window size and game aspect ratio. When scaling by
+    /// whole numbers, the aspect ratio is taken as the size of the game in pixels, and the game is
+    /// stretched to fit like otherwise if it doesn't fit at its native size.
     pub fn rescale(
         &mut self,
         win_size: &winit::dpi::PhysicalSize<u32>,
@@ -31,6 +49,23 @@ impl Scaling {
     ) {
         let width = win_size.width as f32;
         let height = win_size.height as f32;
+        self.translation = cgmath::Vector2::new(0.0, 0.0);
+        let whole_scale = (width / aspect_ratio_width)
+            .min(height / aspect_ratio_height)
+            .floor();
+        if self.integer && whole_scale >= 1.0 {
+            let game_width = aspect_ratio_width * whole_scale;
+            let game_height = aspect_ratio_height * whole_scale;
+            self.scaling.x = game_width / width;
+            self.scaling.y = game_height / height;
+            // Centered, but rounded to whole pixels from the top left so each pixel of the game
+            // covers exactly the window's
+            let left = ((width - game_width) / 2.0).floor();
+            let top = ((height - game_height) / 2.0).floor();
+            self.translation.x = (left + game_width / 2.0) / width * 2.0 - 1.0;
+            self.translation.y = 1.0 - (top + game_height / 2.0) / height * 2.0;
+            return;
+        }
         let window_ratio = (width * aspect_ratio_height) / (height * aspect_ratio_width);
         // If the window is too tall for the aspect ratio, scale the x to fit the window and y to
         // keep aspect ratio. Otherwise, scale the y to fit the window and x to keep the
@@ -44,13 +79,72 @@ impl Scaling {
         }
     }
 
+    /// Converts a position in a window of the given size to a position relative to the game, with
+    /// both axes from -1 to 1 over the game. Takes the center of the pixel the position is in,
+    /// since that is where the pixel is sampled when drawing it, so the position is over whatever
+    /// is drawn in that pixel.
+    pub fn window_to_game(
+        &self,
+        pos: &winit::dpi::PhysicalPosition<f64>,
+        win_size: &winit::dpi::PhysicalSize<u32>,
+    ) -> cgmath::Vector2<f32> {
+        let to_game = |pos: f64, size: u32, translation: f32, scaling: f32| {
+            let ndc = (pos.floor() + 0.5) / size as f64 * 2.0 - 1.0;
+            ((ndc - translation as f64) / scaling as f64) as f32
+        };
+        cgmath::vec2(
+            to_game(pos.x, win_size.width, self.translation.x, self.scaling.x),
+            -to_game(pos.y, win_size.height, -self.translation.y, self.scaling.y),
+        )
+    }
+
+    /// Returns the [Scaling] of what the camera looks at, which is zoomed and panned before being
+    /// scaled to fit the window.
+    pub fn through_camera(&self) -> Self {
+        Self {
+            scaling: self.scaling * self.zoom,
+            translation: cgmath::vec2(
+                self.translation.x + self.scaling.x * self.pan.x,
+                self.translation.y + self.scaling.y * self.pan.y,
+            ),
+            zoom: 1.0,
+            pan: cgmath::vec2(0.0, 0.0),
+            ..*self
+        }
+    }
+
+    /// Converts a position relative to the game to the position the camera shows there.
+    pub fn under_camera(&self, pos: cgmath::Vector2<f32>) -> cgmath::Vector2<f32> {
+        (pos - self.pan) / self.zoom
+    }
+
+    /// Returns the columns and rows of the pixels in a window of the given size whose centers are
+    /// inside the given rectangle, given by its bottom left and top right corners relative to the
+    /// game. Pixels with their centers on the top and left edges count but the bottom and right
+    /// ones don't, like when the GPU draws the rectangle.
+    pub fn covered_pixels(
+        &self,
+        bottom_left: cgmath::Vector2<f32>,
+        top_right: cgmath::Vector2<f32>,
+        win_size: &winit::dpi::PhysicalSize<u32>,
+    ) -> [Range<u32>; 2] {
+        let (width, height) = (win_size.width as f32, win_size.height as f32);
+        let to_x = |ndc: f32| (ndc * self.scaling.x + self.translation.x + 1.0) / 2.0 * width;
+        let to_y = |ndc: f32| (1.0 - ndc * self.scaling.y - self.translation.y) / 2.0 * height;
+        let first_pixel = |edge: f32| (edge - 0.5).ceil().max(0.0) as u32;
+        [
+            first_pixel(to_x(bottom_left.x))..first_pixel(to_x(top_right.x)).min(win_size.width),
+            first_pixel(to_y(top_right.y))..first_pixel(to_y(bottom_left.y)).min(win_size.height),
+        ]
+    }
+
     /// Build a scaling matrix using the given camera.
     fn build_scaling_matrix(&self) -> [[f32; 4]; 4] {
         [
             [self.scaling.x, 0.0, 0.0, 0.0],
             [0.0, self.scaling.y, 0.0, 0.0],
             [0.0, 0.0, 1.0, 0.0],
-            [0.0, 0.0, 0.0, 1.0],
+            [self.translation.x, self.translation.y, 0.0, 1.0],
         ]
     }
 }
@@ -58,7 +152,7 @@ impl Scaling {
 /// Stores info on how to scale each instance to fit the window as a 4x4 scaling matrix.
 /// Uses #[repr(C)] for wgsl shader compatability.
 #[repr(C)]
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct ScalingUniform {
     scaling: [[f32; 4]; 4],
 }
@@ -70,6 +164,12 @@ impl ScalingUniform {
             scaling: camera.build_scaling_matrix(),
         }
     }
+
+    /// Creates a new [ScalingUniform] that also zooms and pans like the camera of the given
+    /// [Scaling].
+    pub fn through_camera(camera: &Scaling) -> Self {
+        Self::new(&camera.through_camera())
+    }
 }
 
 /// A vertex from a mesh.
@@ -106,6 +206,9 @@ pub struct Instance {
     pub vertex_scale: [f32; 2],
     pub tex_coord_translation: [f32; 2],
     pub tex_coord_scale: [f32; 2],
+    /// Color drawn instead of the texture when its alpha is non-zero. Blended with whatever is
+    /// drawn underneath.
+    pub color: [f32; 4],
 }
 
 impl Instance {
@@ -121,6 +224,7 @@ impl Instance {
             vertex_scale,
             tex_coord_translation,
             tex_coord_scale,
+            color: [0.0; 4],
         }
     }
 
@@ -150,30 +254,62 @@ impl Instance {
                     shader_location: 8,
                     format: wgpu::VertexFormat::Float32x2,
                 },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
             ],
         }
     }
 }
 
+/// Most separate ranges of changed [Instance]s kept before they are merged into one, so a big
+/// change is uploaded in one write rather than many small ones.
+const MAX_DIRTY_RANGES: usize = 16;
+
+/// Range of indices covering every [Instance], however many there are by the time they are
+/// uploaded.
+const ALL_INSTANCES: Range<usize> = 0..usize::MAX;
+
 /// Represents a collection of [Instance]s.
 pub struct TextureInstances {
     instances: Vec<Instance>,
+    /// Ranges of indices of the [Instance]s changed since they were last given to a
+    /// [TextureRenderer].
+    dirty: Vec<Range<usize>>,
 }
 
 impl TextureInstances {
     /// Creates a new [TextureInstance] from the given collection of [Instance]s.
     pub fn new(instances: Vec<Instance>) -> Self {
-        Self { instances }
+        Self {
+            instances,
+            dirty: Vec::from([ALL_INSTANCES]),
+        }
     }
 
-    /// Provides a mutable reference to the underlying collection of [Instance]s.
+    /// Provides a mutable reference to the underlying collection of [Instance]s. All of them are
+    /// uploaded again as any of them may change.
     pub fn get_instances(&mut self) -> &mut Vec<Instance> {
+        self.mark_all_dirty();
         &mut self.instances
     }
 
+    /// Provides a reference to the underlying collection of [Instance]s.
+    pub fn as_slice(&self) -> &[Instance] {
+        &self.instances
+    }
+
+    /// Provides a reference to the [Instance] at the given index.
+    pub fn get_instance(&self, index: usize) -> &Instance {
+        &self.instances[index]
+    }
+
     /// Replaces the collection of [Instance]s with the given [Instance]s.
     pub fn set_instances(&mut self, new_instances: Vec<Instance>) {
         self.instances = new_instances;
+        self.mark_all_dirty();
     }
 
     /// Provides a reference to the instance data in a form to be passed to a [TextureRenderer].
@@ -183,7 +319,60 @@ impl TextureInstances {
 
     /// Updates the texture coordinates of the instance at the given index.
     pub fn update_tex_trans_instance(&mut self, index: usize, tex_coord_translation: [f32; 2]) {
+        if self.instances[index].tex_coord_translation == tex_coord_translation {
+            return;
+        }
         self.instances[index].tex_coord_translation = tex_coord_translation;
+        self.mark_dirty(index);
+    }
+
+    /// Updates the texture coordinates and their scale of the instance at the given index.
+    pub fn update_tex_instance(
+        &mut self,
+        index: usize,
+        tex_coord_translation: [f32; 2],
+        tex_coord_scale: [f32; 2],
+    ) {
+        self.update_tex_trans_instance(index, tex_coord_translation);
+        if self.instances[index].tex_coord_scale == tex_coord_scale {
+            return;
+        }
+        self.instances[index].tex_coord_scale = tex_coord_scale;
+        self.mark_dirty(index);
+    }
+
+    /// Records that the instance at the given index changed, extending the last range if it is
+    /// next to it.
+    fn mark_dirty(&mut self, index: usize) {
+        let next_to_last = |last: &&mut Range<usize>| last.start <= index && index <= last.end;
+        if let Some(last) = self.dirty.last_mut().filter(next_to_last) {
+            last.end = last.end.max(index + 1);
+            return;
+        }
+        self.dirty.push(index..index + 1);
+        if self.dirty.len() > MAX_DIRTY_RANGES {
+            let start = self
+                .dirty
+                .iter()
+                .map(|range| range.start)
+                .min()
+                .unwrap_or(0);
+            let end = self.dirty.iter().map(|range| range.end).max().unwrap_or(0);
+            self.dirty.clear();
+            self.dirty.push(start..end);
+        }
+    }
+
+    /// Records that every instance changed, including any added before they are uploaded.
+    fn mark_all_dirty(&mut self) {
+        self.dirty.clear();
+        self.dirty.push(ALL_INSTANCES);
+    }
+
+    /// Returns the ranges of indices of the instances changed since the last call and forgets
+    /// them.
+    fn take_dirty(&mut self) -> Vec<Range<usize>> {
+        std::mem::take(&mut self.dirty)
     }
 }
 
@@ -195,6 +384,8 @@ pub struct TextureRenderer {
     atlas_height: u16,
     render_pipeline: Arc<wgpu::RenderPipeline>,
     scaling_bind_group: Arc<wgpu::BindGroup>,
+    /// Layout of the texture bind group, kept to bind another texture in its place.
+    texture_layout: wgpu::BindGroupLayout,
     texture_bind_group: wgpu::BindGroup,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
@@ -203,8 +394,16 @@ pub struct TextureRenderer {
     num_instances: u32,
 }
 
+/// What the buffers of a [TextureRenderer] are filled with when it is created.
+pub struct BufferContents<'a> {
+    pub indices: &'a [u16],
+    /// Instances in the form returned by [TextureInstances::get_data].
+    pub instance_data: &'a [u8],
+    pub vertices: &'a [Vertex],
+}
+
 impl TextureRenderer {
-    /// Creates a new [TextureRenderer].
+    /// Creates a new [TextureRenderer] with buffers holding the given contents.
     pub fn new(
         device: &wgpu::Device,
         render_pipeline: Arc<wgpu::RenderPipeline>,
@@ -212,18 +411,12 @@ impl TextureRenderer {
         bind_group_layout: &wgpu::BindGroupLayout,
         name: String,
         texture: wgpu::Texture,
-        indices: &[u16],
-        instance_data: &[u8],
-        vertices: &[Vertex],
+        BufferContents {
+            indices,
+            instance_data,
+            vertices,
+        }: BufferContents,
     ) -> Self {
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
-
         // Get number of indices
         let num_indices = indices.len() as u32;
 
@@ -245,20 +438,8 @@ impl TextureRenderer {
         });
 
         // Crate bind group
-        let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-            ],
-            label: Some(&*(name.clone() + " Bind Group")),
-        });
+        let texture_bind_group =
+            make_texture_bind_group(device, bind_group_layout, &name, &texture);
 
         TextureRenderer {
             name,
@@ -266,6 +447,7 @@ impl TextureRenderer {
             atlas_height: texture.height() as u16,
             render_pipeline,
             scaling_bind_group,
+            texture_layout: bind_group_layout.clone(),
             texture_bind_group,
             vertex_buffer,
             index_buffer,
@@ -275,29 +457,76 @@ impl TextureRenderer {
         }
     }
 
-    /// Updates the instance buffer to reflect the current state of instances.
-    pub fn prepare(&mut self, instances: &[u8], device: &wgpu::Device, queue: &wgpu::Queue) {
-        if self.instance_buffer.size() as usize >= instances.len() * size_of::<u8>() {
-            queue.write_buffer(&self.instance_buffer, 0, instances);
+    /// Updates the instance buffer to reflect the current state of instances, uploading only the
+    /// instances that changed since the last time unless the buffer has to grow.
+    pub fn prepare(
+        &mut self,
+        instances: &mut TextureInstances,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        let dirty = instances.take_dirty();
+        let data = instances.get_data();
+        if self.instance_buffer.size() as usize >= data.len() {
+            let len = instances.instances.len();
+            for range in dirty {
+                let bytes = range.start.min(len) * size_of::<Instance>()
+                    ..range.end.min(len) * size_of::<Instance>();
+                if !bytes.is_empty() {
+                    queue.write_buffer(
+                        &self.instance_buffer,
+                        bytes.start as wgpu::BufferAddress,
+                        &data[bytes],
+                    );
+                }
+            }
         } else {
             self.instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some(&(self.name.clone() + " Instance Buffer")),
-                contents: instances,
+                contents: data,
                 usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
             });
         }
-        self.num_instances = (instances.len() / size_of::<Instance>()) as u32;
+        self.num_instances = instances.instances.len() as u32;
     }
 
     /// Renders the instances that were previously provided to `prepare`.
     pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        self.render_with(
+            render_pass,
+            &self.scaling_bind_group,
+            0..self.num_instances as usize,
+        );
+    }
+
+    /// Renders the instances in the given range of those previously provided to `prepare`, scaled
+    /// with the given scaling bind group instead of the [TextureRenderer]'s own.
+    pub fn render_with(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        scaling_bind_group: &wgpu::BindGroup,
+        instances: Range<usize>,
+    ) {
+        let instances = instances.start as u32..(instances.end as u32).min(self.num_instances);
+        // Empty buffers can't be bound, and there would be nothing to draw anyway
+        if instances.is_empty() {
+            return;
+        }
         render_pass.set_pipeline(&self.render_pipeline);
         render_pass.set_bind_group(0, &self.texture_bind_group, &[]);
-        render_pass.set_bind_group(1, self.scaling_bind_group.as_ref(), &[]);
+        render_pass.set_bind_group(1, scaling_bind_group, &[]);
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        render_pass.draw_indexed(0..self.num_indices, 0, 0..self.num_instances);
+        render_pass.draw_indexed(0..self.num_indices, 0, instances);
+    }
+
+    /// Draws with the given texture from now on, for when the atlas is replaced.
+    pub fn set_texture(&mut self, device: &wgpu::Device, texture: wgpu::Texture) {
+        self.texture_bind_group =
+            make_texture_bind_group(device, &self.texture_layout, &self.name, &texture);
+        self.atlas_width = texture.width() as u16;
+        self.atlas_height = texture.height() as u16;
     }
 
     /// Returns the width of the [TextureRenderer]'s texture.
@@ -311,17 +540,44 @@ impl TextureRenderer {
     }
 }
 
-/// Creates a texture using the given bytes as an image.
-pub(crate) fn from_bytes(
+/// Creates a bind group sampling the given texture for the [TextureRenderer] with the given name.
+fn make_texture_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    name: &str,
+    texture: &wgpu::Texture,
+) -> wgpu::BindGroup {
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        mag_filter: wgpu::FilterMode::Nearest,
+        min_filter: wgpu::FilterMode::Nearest,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        ..Default::default()
+    });
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            },
+        ],
+        label: Some(&format!("{name} Bind Group")),
+    })
+}
+
+/// Creates a texture holding the given image.
+pub(crate) fn from_image(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
-    bytes: &[u8],
+    rgba: &image::RgbaImage,
     label: Option<&str>,
-) -> Result<wgpu::Texture> {
-    let image = image::load_from_memory(bytes)?;
-
-    let rgba = image.to_rgba8();
-    let dimensions = image.dimensions();
+) -> wgpu::Texture {
+    let dimensions = rgba.dimensions();
 
     let texture_size = wgpu::Extent3d {
         width: dimensions.0,
@@ -360,7 +616,7 @@ pub(crate) fn from_bytes(
             aspect: wgpu::TextureAspect::All,
         },
         // The actual pixel data
-        &rgba,
+        rgba,
         // The layout of the texture
         wgpu::TexelCopyBufferLayout {
             offset: 0,
@@ -370,5 +626,5 @@ pub(crate) fn from_bytes(
         texture_size,
     );
 
-    Ok(texture)
+    texture
 }
@@ -1,10 +1,25 @@
-use rand::Rng;
+use crate::solver;
+use anyhow::{
+    Context,
+    Result,
+    bail,
+    ensure,
+};
+use rand::{
+    Rng,
+    SeedableRng,
+    rngs::StdRng,
+};
 use std::{
     cmp::PartialEq,
+    collections::HashMap,
+    fmt,
     ops::{
         Index,
         IndexMut,
     },
+    str::FromStr,
+    time::Duration,
 };
 
 pub type Row = u8;
@@ -16,6 +31,18 @@ pub type Dim = u8;
 /// Count of elements in a minesweeper grid.
 pub type Count = u16;
 
+/// Limits the number of attempts at placing mines in no-guess mode before settling for a layout
+/// that needs guessing. Divided by the number of cells so huge boards give up in reasonable time.
+const NO_GUESS_CELL_BUDGET: u32 = 50_000;
+/// Minimum number of attempts at placing mines in no-guess mode, no matter the board size.
+const MIN_NO_GUESS_ATTEMPTS: u32 = 10;
+/// Longest time spent placing mines in no-guess mode before settling for a layout that needs
+/// guessing, even in the middle of an attempt. Placing them happens on the first click, so the
+/// window waits for it.
+const NO_GUESS_TIME_LIMIT: Duration = Duration::from_secs(2);
+/// First line of every saved game. Bumped whenever the format changes.
+const SAVE_HEADER: &str = "minesweeper game 1";
+
 /// All the different textures a [Cell] can have.
 #[derive(Clone, Debug, PartialEq)]
 pub enum CellImage {
@@ -55,9 +82,52 @@ impl CellImage {
         }
     }
 
+    /// Returns the number shown by the given CellImage, if it shows one.
+    pub fn number(&self) -> Option<u8> {
+        match self {
+            CellImage::Zero => Some(0),
+            CellImage::One => Some(1),
+            CellImage::Two => Some(2),
+            CellImage::Three => Some(3),
+            CellImage::Four => Some(4),
+            CellImage::Five => Some(5),
+            CellImage::Six => Some(6),
+            CellImage::Seven => Some(7),
+            CellImage::Eight => Some(8),
+            _ => None,
+        }
+    }
+
+    /// Returns the character representing the given CellImage in saved games.
+    fn to_char(&self) -> char {
+        match self {
+            CellImage::Mine => '*',
+            CellImage::WronglyFlagged => 'x',
+            CellImage::SelectedMine => '!',
+            CellImage::Hidden => '#',
+            CellImage::Flagged => 'F',
+            CellImage::QuestionMarked => '?',
+            number => char::from(b'0' + number.number().unwrap()),
+        }
+    }
+
+    /// Converts a character from a saved game back to the CellImage it represents.
+    fn from_char(c: char) -> Option<CellImage> {
+        match c {
+            '*' => Some(CellImage::Mine),
+            'x' => Some(CellImage::WronglyFlagged),
+            '!' => Some(CellImage::SelectedMine),
+            '#' => Some(CellImage::Hidden),
+            'F' => Some(CellImage::Flagged),
+            '?' => Some(CellImage::QuestionMarked),
+            '0'..='8' => Some(CellImage::from_number(c as u8 - b'0')),
+            _ => None,
+        }
+    }
+
     /// Whether the given CellImage is a shown texture. Shown textures represent cells that have
     /// been revealed.
-    fn shown(&self) -> bool {
+    pub fn shown(&self) -> bool {
         match self {
             CellImage::Hidden => false,
             CellImage::Flagged => false,
@@ -96,6 +166,27 @@ impl GameState {
     pub fn is_after_game(&self) -> bool {
         matches!(self, GameState::Victory | GameState::Loss)
     }
+
+    /// Returns the name of the given GameState in saved games.
+    fn name(&self) -> &'static str {
+        match self {
+            GameState::BeforeGame => "before",
+            GameState::DuringGame => "during",
+            GameState::Victory => "victory",
+            GameState::Loss => "loss",
+        }
+    }
+
+    /// Converts a name from a saved game back to the GameState it represents.
+    fn from_name(name: &str) -> Option<GameState> {
+        match name {
+            "before" => Some(GameState::BeforeGame),
+            "during" => Some(GameState::DuringGame),
+            "victory" => Some(GameState::Victory),
+            "loss" => Some(GameState::Loss),
+            _ => None,
+        }
+    }
 }
 
 /// Represents the grid of [Cell]s. Stored as a 2D vector of [Cells] and indexed using [u8] because
@@ -147,6 +238,158 @@ impl IndexMut<Pos> for GameGrid {
     }
 }
 
+/// A suggestion for the next cell to reveal, as given by [Game::hint].
+#[derive(Debug, Clone, Copy)]
+pub enum Hint {
+    /// A cell that is provably safe, along with the proof.
+    Safe(solver::Finding),
+    /// No cell is provably safe, so this is the cell least likely to be a mine.
+    LeastRisky { pos: Pos, mine_probability: f64 },
+}
+
+impl Hint {
+    /// Returns the location of the suggested cell.
+    pub fn pos(&self) -> Pos {
+        match self {
+            Hint::Safe(finding) => finding.pos,
+            Hint::LeastRisky { pos, .. } => *pos,
+        }
+    }
+}
+
+/// How a lost game was lost, as computed by [Game::grade_loss].
+#[derive(Debug, Clone, Copy)]
+pub struct LossGrade {
+    /// Probability the clicked mine was a mine given what the player could see.
+    pub mine_probability: f64,
+    /// Whether every hidden [Cell] had a chance of being a mine, so a guess was unavoidable.
+    pub forced_guess: bool,
+}
+
+/// The clicks made in a game. Clicks made after the game ended aren't counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Clicks {
+    /// Left clicks on hidden, flagged, or question marked [Cell]s.
+    pub left: u32,
+    pub right: u32,
+    /// Left clicks on shown [Cell]s, revealing the [Cell]s around them.
+    pub chords: u32,
+    /// Clicks of any kind that didn't change anything.
+    pub wasted: u32,
+}
+
+impl Clicks {
+    /// Returns the number of clicks of every kind.
+    pub fn total(&self) -> u32 {
+        self.left + self.right + self.chords
+    }
+
+    /// Returns the number of clicks that changed something.
+    pub fn effective(&self) -> u32 {
+        self.total() - self.wasted
+    }
+}
+
+/// The standard competitive metrics of a finished game, as computed by [Game::metrics]. Speed
+/// metrics only count the 3BV that was solved, so they still mean something for lost games.
+#[derive(Debug, Clone, Copy)]
+pub struct Metrics {
+    /// The minimum number of left clicks needed to reveal every safe [Cell], with each opening
+    /// taking one click and each other safe [Cell] taking one click.
+    pub three_bv: u32,
+    /// The part of the 3BV that was revealed.
+    pub solved_three_bv: u32,
+    /// Groups of connected 0s, each revealed along with its border by a single click.
+    pub openings: u32,
+    /// Groups of connected safe [Cell]s that aren't part of or next to an opening.
+    pub islands: u32,
+    pub clicks: Clicks,
+    /// How long the game took.
+    pub time: Duration,
+}
+
+impl Metrics {
+    /// Returns the 3BV solved per second, or [None] if no time passed.
+    pub fn three_bv_per_second(&self) -> Option<f64> {
+        let secs = self.time.as_secs_f64();
+        (secs > 0.0).then(|| self.solved_three_bv as f64 / secs)
+    }
+
+    /// Returns the Index Of Speed, log(3BV) / log(time), which rates speed independently of the
+    /// board's 3BV. Is [None] for games taking a second or less.
+    pub fn ios(&self) -> Option<f64> {
+        let secs = self.time.as_secs_f64();
+        (secs > 1.0 && self.solved_three_bv > 0)
+            .then(|| (self.solved_three_bv as f64).ln() / secs.ln())
+    }
+
+    /// Returns the Rapport Qualité Prix, time / 3BV/s, where lower is better. Is [None] if no 3BV
+    /// was solved.
+    pub fn rqp(&self) -> Option<f64> {
+        (self.solved_three_bv > 0)
+            .then(|| self.time.as_secs_f64().powi(2) / self.solved_three_bv as f64)
+    }
+
+    /// Returns the solved 3BV divided by the total clicks, or [None] if there were no clicks.
+    /// Can go over 1 since flags and chords can save clicks.
+    pub fn efficiency(&self) -> Option<f64> {
+        let clicks = self.clicks.total();
+        (clicks > 0).then(|| self.solved_three_bv as f64 / clicks as f64)
+    }
+}
+
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let metric =
+            |value: Option<f64>| value.map_or("-".to_string(), |value| format!("{value:.3}"));
+        writeln!(
+            f,
+            "3BV: {}/{} ({} openings, {} islands)",
+            self.solved_three_bv, self.three_bv, self.openings, self.islands
+        )?;
+        writeln!(
+            f,
+            "Clicks: {} total, {} effective, {} wasted ({} left, {} right, {} chords)",
+            self.clicks.total(),
+            self.clicks.effective(),
+            self.clicks.wasted,
+            self.clicks.left,
+            self.clicks.right,
+            self.clicks.chords
+        )?;
+        write!(
+            f,
+            "3BV/s: {}, IOS: {}, RQP: {}, efficiency: {}",
+            metric(self.three_bv_per_second()),
+            metric(self.ios()),
+            metric(self.rqp()),
+            self.efficiency()
+                .map_or("-".to_string(), |efficiency| format!(
+                    "{:.1}%",
+                    efficiency * 100.0
+                ))
+        )
+    }
+}
+
+/// A move that changed the game, stored so it can be undone and redone.
+#[derive(Debug, Clone)]
+struct Move {
+    /// Every [Cell] the move changed, as its position and its images before and after the move.
+    changes: Vec<(Pos, CellImage, CellImage)>,
+    game_state: (GameState, GameState),
+    flags: (Count, Count),
+    hidden: (Count, Count),
+}
+
+/// The parts of a [Game] a move can change, taken before the move so it can be recorded.
+struct Snapshot {
+    images: Vec<Vec<CellImage>>,
+    game_state: GameState,
+    flags: Count,
+    hidden: Count,
+}
+
 /// A game of minesweeper. Width and height are stored as [u8] because of obvious usability
 /// issues in minesweeper grid size >255x255. Flags, hidden, and total_mines are [u16] to
 /// account for this.
@@ -159,12 +402,39 @@ pub struct Game {
     pub flags: Count,
     hidden: Count,
     pub total_mines: Count,
+    /// Seed for the random number generator used to place mines. The same seed and first click
+    /// always produce the same layout.
+    pub seed: u64,
+    /// Whether the seed was chosen by the player. If not, a new seed is picked on every reset.
+    fixed_seed: bool,
+    /// Whether mines should only be placed in layouts that can be solved without guessing.
+    pub no_guess: bool,
+    /// Whether no-guess mode ran out of attempts or time and placed mines in a layout that needs
+    /// guessing.
+    pub needs_guessing: bool,
+    /// Number of hints used this game.
+    pub hints: u32,
+    /// Clicks made this game.
+    clicks: Clicks,
+    /// Whether moves can be undone and redone.
+    pub practice: bool,
+    /// Whether left clicking a flagged [Cell] question marks it.
+    pub question_marks: bool,
+    /// Mines placed at the start of every game instead of random ones, if set.
+    layout: Option<Vec<Pos>>,
+    /// The [Cell] clicked to start the game.
+    first_click: Option<Pos>,
+    /// Moves made this game, oldest first. Only kept in practice mode.
+    history: Vec<Move>,
+    /// Moves that were undone, most recently undone last.
+    undone: Vec<Move>,
 }
 
 impl Game {
-    /// Creates a new game of minesweeper with the given dimensions and number of mines. Panics if
-    /// the inputs are invalid.
-    pub fn new(width: Dim, height: Dim, mines: Count) -> Self {
+    /// Creates a new game of minesweeper with the given dimensions and number of mines. If a seed
+    /// is given, every game uses it to place mines. Otherwise, a random seed is picked for each
+    /// game. Panics if the inputs are invalid.
+    pub fn new(width: Dim, height: Dim, mines: Count, seed: Option<u64>) -> Self {
         assert!(
             width as u16 * height as u16 > mines && width != 0 && height != 0 && mines != 0,
             "Invalid grid"
@@ -177,6 +447,18 @@ impl Game {
             flags: 0,
             hidden: width as Count * height as Count,
             total_mines: mines,
+            seed: seed.unwrap_or_else(rand::random),
+            fixed_seed: seed.is_some(),
+            no_guess: false,
+            needs_guessing: false,
+            hints: 0,
+            clicks: Clicks::default(),
+            practice: false,
+            question_marks: true,
+            layout: None,
+            first_click: None,
+            history: Vec::new(),
+            undone: Vec::new(),
         }
     }
 
@@ -186,12 +468,21 @@ impl Game {
         self.width = width;
         self.height = height;
         self.total_mines = num_mines;
+        self.layout = None;
     }
 
-    /// Resets the game.
+    /// Resets the game. Picks a new seed unless the seed was chosen by the player.
     pub fn reset(&mut self) {
         self.flags = 0;
+        self.hints = 0;
+        self.clicks = Clicks::default();
+        self.first_click = None;
+        self.history.clear();
+        self.undone.clear();
         self.game_state = GameState::BeforeGame;
+        if !self.fixed_seed {
+            self.seed = rand::random();
+        }
     }
 
     /// Performs the left click operations for minesweeper. Reveals the given [Cell] if it has the
@@ -208,19 +499,29 @@ impl Game {
         if self.game_state == GameState::BeforeGame {
             self.start_game(pos);
         }
+        let before = self.snapshot();
         let cell = &mut self.grid[pos];
         if self.game_state == GameState::DuringGame {
             if cell.image == CellImage::Hidden {
+                self.clicks.left += 1;
                 result = self.show(vec![pos]);
             } else if !cell.image.shown() {
-                result.push(self.toggle_tofrom_question_marked(pos));
+                self.clicks.left += 1;
+                if self.question_marks || cell.image == CellImage::QuestionMarked {
+                    result.push(self.toggle_tofrom_question_marked(pos));
+                }
             } else {
+                self.clicks.chords += 1;
                 result = self.show(self.get_hidden_neighbors(pos));
             }
+            if result.is_empty() {
+                self.clicks.wasted += 1;
+            }
             if self.hidden == self.total_mines {
                 result.append(&mut self.handle_win());
             }
         }
+        self.record(before, &result);
         result
     }
 
@@ -234,13 +535,101 @@ impl Game {
             pos
         );
         // Does nothing if the cell is shown, otherwise toggle the flag
-        if !(self.game_state == GameState::DuringGame) || self.grid[pos].image.shown() {
+        if !(self.game_state == GameState::DuringGame) {
+            None
+        } else if self.grid[pos].image.shown() {
+            self.clicks.right += 1;
+            self.clicks.wasted += 1;
             None
         } else {
-            Some(self.toggle_tofrom_hidden(pos))
+            self.clicks.right += 1;
+            let before = self.snapshot();
+            let update = self.toggle_tofrom_hidden(pos);
+            self.record(before, std::slice::from_ref(&update));
+            Some(update)
         }
     }
 
+    /// Undoes the last move made. Undoing the move that ended the game resumes it.
+    /// Returns a list of tuples giving the row, column, and [CellImage] for every [Cell] texture
+    /// updated. Does nothing outside of practice mode.
+    pub fn undo(&mut self) -> Vec<(Pos, CellImage)> {
+        let Some(undone) = self.history.pop() else {
+            return Vec::new();
+        };
+        self.game_state = undone.game_state.0;
+        self.flags = undone.flags.0;
+        self.hidden = undone.hidden.0;
+        let result = undone
+            .changes
+            .iter()
+            .rev()
+            .map(|(pos, before, _)| {
+                self.grid[*pos].image = before.clone();
+                (*pos, before.clone())
+            })
+            .collect();
+        self.undone.push(undone);
+        result
+    }
+
+    /// Redoes the last move undone. Returns a list of tuples giving the row, column, and
+    /// [CellImage] for every [Cell] texture updated. Does nothing if no move was undone since the
+    /// last move was made.
+    pub fn redo(&mut self) -> Vec<(Pos, CellImage)> {
+        let Some(redone) = self.undone.pop() else {
+            return Vec::new();
+        };
+        self.game_state = redone.game_state.1;
+        self.flags = redone.flags.1;
+        self.hidden = redone.hidden.1;
+        let result = redone
+            .changes
+            .iter()
+            .map(|(pos, _, after)| {
+                self.grid[*pos].image = after.clone();
+                (*pos, after.clone())
+            })
+            .collect();
+        self.history.push(redone);
+        result
+    }
+
+    /// Returns the state needed to record a move, if moves are being recorded.
+    fn snapshot(&self) -> Option<Snapshot> {
+        self.practice.then(|| Snapshot {
+            images: self.get_all_images(),
+            game_state: self.game_state,
+            flags: self.flags,
+            hidden: self.hidden,
+        })
+    }
+
+    /// Records the move made since the given snapshot was taken if it changed anything.
+    /// Moves made after undoing can't be redone past.
+    fn record(&mut self, before: Option<Snapshot>, updates: &[(Pos, CellImage)]) {
+        let Some(before) = before.filter(|_| !updates.is_empty()) else {
+            return;
+        };
+        let changes = updates
+            .iter()
+            .map(|((row, col), after)| {
+                (
+                    (*row, *col),
+                    before.images[*row as usize][*col as usize].clone(),
+                    after.clone(),
+                )
+            })
+            .collect();
+        self.history.push(Move {
+            changes,
+            game_state: (before.game_state, self.game_state),
+            flags: (before.flags, self.flags),
+            hidden: (before.hidden, self.hidden),
+        });
+        self.undone.clear();
+    }
+
     /// Reveal the given [Cell]s and returns a list of tuples giving the row, column, and
     /// [CellImage] for every [Cell] texture updated. Performs 0 propagation.
     fn show(&mut self, mut cells: Vec<Pos>) -> Vec<(Pos, CellImage)> {
@@ -380,17 +769,444 @@ impl Game {
         result
     }
 
+    /// Returns the [CellImage]s the player saw before the game ended. Mines revealed by a loss
+    /// are hidden again and wrong flags are shown as flags.
+    fn get_player_images(&self) -> Vec<Vec<CellImage>> {
+        let mut result = self.get_all_images();
+        result.iter_mut().flatten().for_each(|image| {
+            *image = match image {
+                CellImage::Mine | CellImage::SelectedMine => CellImage::Hidden,
+                CellImage::WronglyFlagged => CellImage::Flagged,
+                _ => image.clone(),
+            }
+        });
+        result
+    }
+
+    /// Returns the exact probability that each hidden [Cell] is a mine given the numbers the
+    /// player can see and the total number of mines. Shown [Cell]s are [None]. Returns [None]
+    /// instead if the probabilities are too expensive to compute.
+    pub fn mine_probabilities(&self) -> Option<Vec<Vec<Option<f64>>>> {
+        solver::mine_probabilities(&self.get_player_images(), self.total_mines)
+    }
+
+    /// Suggests a hidden [Cell] to reveal next: one that is provably safe if there is one, or the
+    /// one least likely to be a mine if not. Counts as a hint used if a suggestion is made.
+    /// Returns [None] if the game isn't running or nothing can be suggested.
+    pub fn hint(&mut self) -> Option<Hint> {
+        if self.game_state != GameState::DuringGame {
+            return None;
+        }
+        let images = self.get_all_images();
+        let hint = solver::solve(&images, self.total_mines)
+            .into_iter()
+            .find(|finding| finding.deduction == solver::Deduction::Safe)
+            .map(Hint::Safe)
+            .or_else(|| {
+                let probabilities = self.mine_probabilities()?;
+                let (pos, mine_probability) = (0..self.height)
+                    .flat_map(|row| (0..self.width).map(move |col| (row, col)))
+                    .filter(|pos| self.grid[*pos].image != CellImage::Flagged)
+                    .filter_map(|(row, col)| {
+                        probabilities[row as usize][col as usize]
+                            .map(|probability| ((row, col), probability))
+                    })
+                    .min_by(|(_, first), (_, second)| first.total_cmp(second))?;
+                Some(Hint::LeastRisky {
+                    pos,
+                    mine_probability,
+                })
+            })?;
+        self.hints += 1;
+        Some(hint)
+    }
+
+    /// Whether any hints were used this game. Hinted games don't count toward records.
+    pub fn is_hinted(&self) -> bool {
+        self.hints > 0
+    }
+
+    /// Grades a lost game by how likely the clicked mine was to be a mine and whether any
+    /// [Cell] was provably safe at the time. Returns [None] if the game wasn't lost or the
+    /// probabilities are too expensive to compute.
+    pub fn grade_loss(&self) -> Option<LossGrade> {
+        if self.game_state != GameState::Loss {
+            return None;
+        }
+        let (row, col) = (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |col| (row, col)))
+            .find(|pos| self.grid[*pos].image == CellImage::SelectedMine)?;
+        let probabilities = self.mine_probabilities()?;
+        let forced_guess = probabilities
+            .iter()
+            .flatten()
+            .flatten()
+            .all(|probability| *probability > 0.0);
+        Some(LossGrade {
+            mine_probability: probabilities[row as usize][col as usize]?,
+            forced_guess,
+        })
+    }
+
+    /// Makes every game use mines at the given positions instead of random ones. The first click
+    /// is no longer guaranteed to be safe. Resets the game.
+    pub fn set_layout(&mut self, mines: &[Pos]) {
+        self.reset();
+        self.total_mines = mines.len() as Count;
+        self.layout = Some(mines.to_vec());
+    }
+
+    /// Returns the game as text that [Game::from_save_str] can restore, including the mines. Move
+    /// history isn't saved, so moves made before saving can't be undone after restoring.
+    pub fn to_save_string(&self) -> String {
+        let mut result = String::new();
+        let mut line = |line: String| {
+            result.push_str(&line);
+            result.push('\n');
+        };
+        line(SAVE_HEADER.to_string());
+        line(format!("width {}", self.width));
+        line(format!("height {}", self.height));
+        line(format!("mines {}", self.total_mines));
+        line(format!("seed {}", self.seed));
+        line(format!("fixed_seed {}", self.fixed_seed));
+        line(format!("no_guess {}", self.no_guess));
+        line(format!("practice {}", self.practice));
+        line(format!("question_marks {}", self.question_marks));
+        line(format!("hints {}", self.hints));
+        line(format!(
+            "clicks {} {} {} {}",
+            self.clicks.left, self.clicks.right, self.clicks.chords, self.clicks.wasted
+        ));
+        line(format!("state {}", self.game_state.name()));
+        line(format!("flags {}", self.flags));
+        line(format!("hidden {}", self.hidden));
+        if let Some((row, col)) = self.first_click {
+            line(format!("first_click {row} {col}"));
+        }
+        if self.game_state != GameState::BeforeGame {
+            line("grid".to_string());
+            for row in self.grid.data.iter() {
+                line(row.iter().map(|cell| cell.image.to_char()).collect());
+            }
+            line("mines".to_string());
+            for row in self.grid.data.iter() {
+                line(
+                    row.iter()
+                        .map(|cell| if cell.mine { '*' } else { '.' })
+                        .collect(),
+                );
+            }
+        }
+        result
+    }
+
+    /// Restores a game from text returned by [Game::to_save_string]. Fails if the saved counts
+    /// don't match the grid.
+    pub fn from_save_str(s: &str) -> Result<Game> {
+        let mut lines = s.lines().map(str::trim).enumerate();
+        let (_, header) = lines.next().context("empty saved game")?;
+        ensure!(
+            header == SAVE_HEADER,
+            "not a saved game or unsupported version: {header}"
+        );
+
+        // Read the settings up to the grid
+        let mut values = HashMap::new();
+        let mut has_grid = false;
+        for (idx, line) in lines.by_ref() {
+            if line == "grid" {
+                has_grid = true;
+                break;
+            }
+            let (key, value) = line
+                .split_once(' ')
+                .with_context(|| format!("line {}: expected a key and a value", idx + 1))?;
+            values.insert(key, value);
+        }
+        fn get<T: FromStr>(values: &HashMap<&str, &str>, key: &str) -> Result<T> {
+            let value = values.get(key).with_context(|| format!("missing {key}"))?;
+            value
+                .parse()
+                .ok()
+                .with_context(|| format!("invalid {key}: {value}"))
+        }
+        let width: Dim = get(&values, "width")?;
+        let height: Dim = get(&values, "height")?;
+        let total_mines: Count = get(&values, "mines")?;
+        ensure!(
+            width != 0 && height != 0 && total_mines != 0,
+            "board must not be empty"
+        );
+        ensure!(
+            (total_mines as usize) < width as usize * height as usize,
+            "too many mines for the board"
+        );
+        let game_state = get::<String>(&values, "state")?;
+        let game_state = GameState::from_name(&game_state)
+            .with_context(|| format!("invalid state: {game_state}"))?;
+        let first_click = match values.get("first_click") {
+            Some(value) => {
+                let (row, col) = value
+                    .split_once(' ')
+                    .context("first_click needs a row and a column")?;
+                let (row, col): Pos = (row.parse()?, col.parse()?);
+                ensure!(
+                    row < height && col < width,
+                    "first_click outside of the board"
+                );
+                Some((row, col))
+            }
+            None => None,
+        };
+        let clicks = match values.get("clicks") {
+            Some(value) => {
+                let counts = value
+                    .split_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<Vec<u32>, _>>()
+                    .context("invalid clicks")?;
+                let [left, right, chords, wasted] = counts[..] else {
+                    bail!("clicks needs left, right, chord, and wasted counts");
+                };
+                Clicks {
+                    left,
+                    right,
+                    chords,
+                    wasted,
+                }
+            }
+            None => Clicks::default(),
+        };
+
+        let mut game = Game::new(width, height, total_mines, Some(get(&values, "seed")?));
+        game.fixed_seed = get(&values, "fixed_seed")?;
+        game.no_guess = get(&values, "no_guess")?;
+        game.practice = get(&values, "practice")?;
+        if values.contains_key("question_marks") {
+            game.question_marks = get(&values, "question_marks")?;
+        }
+        game.hints = get(&values, "hints")?;
+        game.clicks = clicks;
+        game.game_state = game_state;
+        game.first_click = first_click;
+        if game_state == GameState::BeforeGame {
+            return Ok(game);
+        }
+
+        // Read the images and then the mines of every cell
+        ensure!(has_grid, "missing grid");
+        game.grid.resize(width, height);
+        for section in ["grid", "mines"] {
+            if section == "mines" {
+                let (idx, line) = lines.next().context("missing mines")?;
+                ensure!(line == "mines", "line {}: expected mines", idx + 1);
+            }
+            for row in 0..height {
+                let (idx, line) = lines
+                    .next()
+                    .with_context(|| format!("{section} is missing rows"))?;
+                ensure!(
+                    line.chars().count() == width as usize,
+                    "line {}: expected {width} cells",
+                    idx + 1
+                );
+                for (col, c) in line.chars().enumerate() {
+                    let cell = &mut game.grid[(row, col as Col)];
+                    match (section, c) {
+                        ("grid", _) => {
+                            cell.image = CellImage::from_char(c)
+                                .with_context(|| format!("line {}: invalid cell: {c}", idx + 1))?
+                        }
+                        (_, '*') => cell.mine = true,
+                        (_, '.') => cell.mine = false,
+                        _ => bail!("line {}: invalid mine: {c}", idx + 1),
+                    }
+                }
+            }
+        }
+
+        // Count the flags and hidden cells from the grid rather than trusting the saved counts
+        let cells = game.grid.data.iter().flatten();
+        ensure!(
+            cells.clone().filter(|cell| cell.mine).count() == total_mines as usize,
+            "mines doesn't match the mines in the grid"
+        );
+        ensure!(
+            !cells
+                .clone()
+                .any(|cell| cell.mine && cell.image.number().is_some()),
+            "a revealed number is a mine"
+        );
+        game.flags = cells
+            .clone()
+            .filter(|cell| matches!(cell.image, CellImage::Flagged | CellImage::WronglyFlagged))
+            .count() as Count;
+        game.hidden = cells.filter(|cell| cell.image.number().is_none()).count() as Count;
+        ensure!(
+            get::<Count>(&values, "flags")? == game.flags,
+            "flags doesn't match the flagged cells in the grid"
+        );
+        ensure!(
+            get::<Count>(&values, "hidden")? == game.hidden,
+            "hidden doesn't match the hidden cells in the grid"
+        );
+        Ok(game)
+    }
+
+    /// Returns the [Pos] clicked to start the game, if it has started.
+    pub fn first_click(&self) -> Option<Pos> {
+        self.first_click
+    }
+
+    /// Returns the location of every mine, row by row. Empty if the game hasn't started.
+    pub fn mine_positions(&self) -> Vec<Pos> {
+        if self.game_state == GameState::BeforeGame {
+            return Vec::new();
+        }
+        (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |col| (row, col)))
+            .filter(|pos| self.grid[*pos].mine)
+            .collect()
+    }
+
+    /// Returns the 3BV of the board: the minimum number of left clicks needed to reveal every safe
+    /// [Cell]. Is 0 if the game hasn't started.
+    pub fn three_bv(&self) -> u32 {
+        self.metrics(Duration::ZERO).three_bv
+    }
+
+    /// Returns the board's difficulty and the player's performance, given how long the game took.
+    /// Board metrics are 0 if the game hasn't started.
+    pub fn metrics(&self, time: Duration) -> Metrics {
+        let mut metrics = Metrics {
+            three_bv: 0,
+            solved_three_bv: 0,
+            openings: 0,
+            islands: 0,
+            clicks: self.clicks,
+            time,
+        };
+        if self.game_state == GameState::BeforeGame {
+            return metrics;
+        }
+        let mut counted = vec![vec![false; self.width as usize]; self.height as usize];
+        let positions = (0..self.height).flat_map(|row| (0..self.width).map(move |col| (row, col)));
+
+        // Count openings, marking every cell they reveal. An opening is solved once any of its 0s
+        // is shown since revealing one reveals the whole opening.
+        for pos in positions.clone() {
+            if counted[pos.0 as usize][pos.1 as usize]
+                || self.grid[pos].mine
+                || self.get_mines_around(pos) != 0
+            {
+                continue;
+            }
+            metrics.openings += 1;
+            if self.grid[pos].image.number().is_some() {
+                metrics.solved_three_bv += 1;
+            }
+            counted[pos.0 as usize][pos.1 as usize] = true;
+            let mut opening = vec![pos];
+            while let Some(pos) = opening.pop() {
+                for neighbor in self.get_neighbors(pos) {
+                    if !counted[neighbor.0 as usize][neighbor.1 as usize] {
+                        counted[neighbor.0 as usize][neighbor.1 as usize] = true;
+                        if self.get_mines_around(neighbor) == 0 {
+                            opening.push(neighbor);
+                        }
+                    }
+                }
+            }
+        }
+        metrics.three_bv = metrics.openings;
+
+        // Count the rest of the safe cells one by one, grouping connected ones into islands
+        for pos in positions {
+            if counted[pos.0 as usize][pos.1 as usize] || self.grid[pos].mine {
+                continue;
+            }
+            metrics.islands += 1;
+            counted[pos.0 as usize][pos.1 as usize] = true;
+            let mut island = vec![pos];
+            while let Some(pos) = island.pop() {
+                metrics.three_bv += 1;
+                if self.grid[pos].image.number().is_some() {
+                    metrics.solved_three_bv += 1;
+                }
+                for neighbor in self.get_neighbors(pos) {
+                    if !counted[neighbor.0 as usize][neighbor.1 as usize]
+                        && !self.grid[neighbor].mine
+                    {
+                        counted[neighbor.0 as usize][neighbor.1 as usize] = true;
+                        island.push(neighbor);
+                    }
+                }
+            }
+        }
+        metrics
+    }
+
     /// Starts the game of minesweeper: resizes the grid to widthxheight, fills the grid with
     /// mines, and changes the [GameState] to [GameState::DuringGame]. A mine will never be
     /// placed in the given row and col and the surrounding [cell]s will be avoided if possible.
-    fn start_game(&mut self, (row, col): Pos) {
+    /// Mines are placed using an rng seeded with the game's seed. In no-guess mode, layouts are
+    /// repaired or placed again until one can be solved without guessing. If a layout was set,
+    /// its mines are used instead.
+    fn start_game(&mut self, pos: Pos) {
         self.game_state = GameState::DuringGame;
+        self.first_click = Some(pos);
         self.hidden = self.height as u16 * self.width as u16;
         self.flags = 0;
+        self.needs_guessing = false;
+        //If the grid is the wrong size, resize it
+        self.grid.resize(self.width, self.height);
+        if let Some(layout) = &self.layout {
+            self.grid.data.iter_mut().flatten().for_each(|cell| {
+                cell.image = CellImage::Hidden;
+                cell.mine = false;
+            });
+            for mine in layout.clone() {
+                self.grid[mine].mine = true;
+            }
+            return;
+        }
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        self.place_mines(pos, &mut rng);
+        if self.no_guess {
+            // Attempts are counted rather than timed so layouts only depend on the seed and first
+            // click. The deadline only stops boards too big to solve in reasonable time.
+            let num_cells = self.width as u32 * self.height as u32;
+            let max_attempts = (NO_GUESS_CELL_BUDGET / num_cells).max(MIN_NO_GUESS_ATTEMPTS);
+            let deadline = std::time::Instant::now() + NO_GUESS_TIME_LIMIT;
+            let mut attempts = 1;
+            let mut stuck = self.find_guess_from(self.hidden_images(), vec![pos], deadline);
+            while let Some(mut images) = stuck {
+                if attempts == max_attempts || std::time::Instant::now() >= deadline {
+                    log::warn!("No layout without guessing found in {attempts} attempts");
+                    self.needs_guessing = true;
+                    break;
+                }
+                attempts += 1;
+                stuck = if self.repair_mines(&images, &mut rng) {
+                    // Continue from where the player got stuck. Once they clear the board, check
+                    // the layout from the start since repairs can invalidate earlier deductions.
+                    let cells = self.refresh_numbers(&mut images);
+                    self.find_guess_from(images, cells, deadline)
+                        .or_else(|| self.find_guess_from(self.hidden_images(), vec![pos], deadline))
+                } else {
+                    self.place_mines(pos, &mut rng);
+                    self.find_guess_from(self.hidden_images(), vec![pos], deadline)
+                };
+            }
+        }
+    }
+
+    /// Fills the grid with mines and resets all [Cell]s to be hidden. A mine will never be placed
+    /// in the given row and col and the surrounding [cell]s will be avoided if possible.
+    fn place_mines(&mut self, (row, col): Pos, rng: &mut StdRng) {
         let width = self.width;
         let height = self.height;
-        //If the grid is the wrong size, resize it
-        self.grid.resize(width, height);
         // Finds all cells that should not be mines
         let mut safe_cells = self.get_3x3((row, col));
         safe_cells
@@ -399,7 +1215,6 @@ impl Game {
         // Remove cells from safe array if needed to get desired number of mines
         let mut cells_remaining = self.hidden - safe_cells.len() as u16;
         let mut mines_remaining = self.total_mines;
-        let mut rng = rand::rng();
         let (first_special_row, first_special_col) = safe_cells[0];
         let (last_special_row, last_special_col) = *safe_cells.iter().max().unwrap();
         let (next_normal_row, next_normal_col) = (last_special_row + 1, last_special_col + 1);
@@ -440,6 +1255,112 @@ impl Game {
         fill_with_mines(next_normal_row..height, 0..width);
     }
 
+    /// Simulates a player who only ever reveals cells the [solver] proves to be safe, starting
+    /// from the given images by revealing the given [Cell]s. Returns the images the player got
+    /// stuck at, or [None] if they cleared the board. Counts as stuck if the solver runs past the
+    /// given deadline.
+    fn find_guess_from(
+        &self,
+        mut images: Vec<Vec<CellImage>>,
+        mut cells: Vec<Pos>,
+        deadline: std::time::Instant,
+    ) -> Option<Vec<Vec<CellImage>>> {
+        let mut hidden = images
+            .iter()
+            .flatten()
+            .filter(|image| image.number().is_none())
+            .count() as Count;
+        loop {
+            // Reveal cells in stack until empty, performing 0 propagation
+            while let Some(pos) = cells.pop() {
+                let image = &mut images[pos.0 as usize][pos.1 as usize];
+                if *image != CellImage::Hidden {
+                    continue;
+                }
+                hidden -= 1;
+                let mines_around = self.get_mines_around(pos);
+                *image = CellImage::from_number(mines_around);
+                if mines_around == 0 {
+                    cells.append(&mut self.get_neighbors(pos));
+                }
+            }
+            if hidden == self.total_mines {
+                return None;
+            }
+            // Reveal everything the solver can prove to be safe
+            let Some(findings) = solver::solve_before(&images, self.total_mines, deadline) else {
+                return Some(images);
+            };
+            cells = findings
+                .into_iter()
+                .filter(|finding| finding.deduction == solver::Deduction::Safe)
+                .map(|finding| finding.pos)
+                .collect();
+            if cells.is_empty() {
+                return Some(images);
+            }
+        }
+    }
+
+    /// Returns images for a grid where every [Cell] is hidden.
+    fn hidden_images(&self) -> Vec<Vec<CellImage>> {
+        vec![vec![CellImage::Hidden; self.width as usize]; self.height as usize]
+    }
+
+    /// Updates the numbers in the given images to match the current mine layout. Returns the
+    /// hidden neighbors of any number that became 0 so they can be revealed.
+    fn refresh_numbers(&self, images: &mut [Vec<CellImage>]) -> Vec<Pos> {
+        let mut cells = Vec::new();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let pos = (row, col);
+                let image = &mut images[row as usize][col as usize];
+                if image.number().is_some() {
+                    let mines_around = self.get_mines_around(pos);
+                    *image = CellImage::from_number(mines_around);
+                    if mines_around == 0 {
+                        cells.append(&mut self.get_neighbors(pos));
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    /// Moves the mines next to the revealed cells in the given images to random cells away from
+    /// anything revealed, so a player stuck at those images has more to go on. Returns whether
+    /// any mine could be moved.
+    fn repair_mines(&mut self, images: &[Vec<CellImage>], rng: &mut StdRng) -> bool {
+        let is_revealed = |(row, col): Pos| images[row as usize][col as usize].number().is_some();
+        let mut stuck_mines = Vec::new();
+        let mut free_cells = Vec::new();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let pos = (row, col);
+                if is_revealed(pos) {
+                    continue;
+                }
+                let next_to_revealed = self.get_neighbors(pos).into_iter().any(is_revealed);
+                if next_to_revealed && self.grid[pos].mine {
+                    stuck_mines.push(pos);
+                } else if !next_to_revealed && !self.grid[pos].mine {
+                    free_cells.push(pos);
+                }
+            }
+        }
+        let mut moved = false;
+        for pos in stuck_mines {
+            if free_cells.is_empty() {
+                break;
+            }
+            let target = free_cells.swap_remove(rng.random_range(0..free_cells.len()));
+            self.grid[pos].mine = false;
+            self.grid[target].mine = true;
+            moved = true;
+        }
+        moved
+    }
+
     /// Returns the locations of all adjacent [Cell]s with [CellImage::Hidden].
     fn get_hidden_neighbors(&self, pos: Pos) -> Vec<Pos> {
         self.get_neighbors(pos)
@@ -494,3 +1415,135 @@ impl Game {
         num_mines
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a game that places mines at the given positions.
+    fn with_layout(width: Dim, height: Dim, mines: &[Pos]) -> Game {
+        let mut game = Game::new(width, height, mines.len() as Count, Some(0));
+        game.set_layout(mines);
+        game
+    }
+
+    #[test]
+    fn grade_loss() {
+        // Revealing the two 1s leaves the board of the weighted probabilities test in solver.rs, so
+        // each cell off the frontier had a quarter chance of being a mine and none was safe
+        let mut game = with_layout(8, 1, &[(0, 2), (0, 6)]);
+        game.left_click((0, 1));
+        game.left_click((0, 3));
+        assert!(game.grade_loss().is_none());
+        game.left_click((0, 6));
+        let grade = game.grade_loss().unwrap();
+        assert!((grade.mine_probability - 0.25).abs() < 1e-9);
+        assert!(grade.forced_guess);
+
+        // The mine next to the 1 is all there is, so the last cell was safe
+        let mut game = with_layout(4, 1, &[(0, 0)]);
+        game.left_click((0, 1));
+        game.left_click((0, 0));
+        let grade = game.grade_loss().unwrap();
+        assert!((grade.mine_probability - 0.5).abs() < 1e-9);
+        assert!(!grade.forced_guess);
+    }
+
+    #[test]
+    fn inconsistent_saves_are_rejected() {
+        let mut game = with_layout(4, 1, &[(0, 0), (0, 3)]);
+        game.left_click((0, 1));
+        game.right_click((0, 0));
+        let save = game.to_save_string();
+        let loaded = Game::from_save_str(&save).unwrap();
+        assert_eq!((loaded.flags, loaded.hidden), (1, 3));
+
+        for (from, to) in [
+            ("flags 1", "flags 0"),
+            ("hidden 3", "hidden 2"),
+            ("\nF1##\n", "\n#1##\n"),
+            ("\n*..*\n", "\n*...\n"),
+            ("\n*..*\n", "\n.*.*\n"),
+            ("first_click 0 1", "first_click 0 4"),
+            ("first_click 0 1", "first_click 1 0"),
+        ] {
+            assert!(save.contains(from), "{from}");
+            let save = save.replacen(from, to, 1);
+            assert!(Game::from_save_str(&save).is_err(), "{to}");
+        }
+    }
+
+    /// Returns the 3BV, openings, and islands of the board, solved 3BV first.
+    fn board_metrics(game: &Game) -> (u32, u32, u32, u32) {
+        let metrics = game.metrics(Duration::ZERO);
+        (
+            metrics.solved_three_bv,
+            metrics.three_bv,
+            metrics.openings,
+            metrics.islands,
+        )
+    }
+
+    #[test]
+    fn metrics() {
+        // 0 0 1 * 1 0 0 has an opening on each side of the mine, each taking in its 1
+        let mut game = with_layout(7, 1, &[(0, 3)]);
+        game.left_click((0, 0));
+        assert_eq!(board_metrics(&game), (1, 2, 2, 0));
+
+        // 1 * 2 * 1 has no 0s, so each number is an island of its own
+        let mut game = with_layout(5, 1, &[(0, 1), (0, 3)]);
+        game.left_click((0, 0));
+        assert_eq!(board_metrics(&game), (1, 3, 0, 3));
+
+        // Every cell around a lone mine is a 1, all connected into one island worth 8 clicks
+        let mut game = with_layout(3, 3, &[(1, 1)]);
+        game.left_click((0, 0));
+        assert_eq!(board_metrics(&game), (1, 8, 0, 1));
+
+        // 1 * 1 0 0 has one opening and the 1 left of the mine as an island
+        let mut game = with_layout(5, 1, &[(0, 1)]);
+        assert_eq!(board_metrics(&game), (0, 0, 0, 0));
+        game.left_click((0, 3));
+        assert_eq!(board_metrics(&game), (1, 2, 1, 1));
+        game.left_click((0, 0));
+        assert_eq!(game.game_state, GameState::Victory);
+        let metrics = game.metrics(Duration::from_secs(4));
+        assert_eq!((metrics.solved_three_bv, metrics.three_bv), (2, 2));
+        assert_eq!(metrics.three_bv_per_second(), Some(0.5));
+        assert_eq!(metrics.ios(), Some(0.5));
+        assert_eq!(metrics.rqp(), Some(8.0));
+        assert_eq!(metrics.efficiency(), Some(1.0));
+    }
+
+    #[test]
+    fn no_guess_boards_can_be_solved() {
+        for seed in 0..10 {
+            let mut game = Game::new(16, 16, 40, Some(seed));
+            game.no_guess = true;
+            game.left_click((8, 8));
+            assert!(!game.needs_guessing, "seed {seed}");
+
+            // The same seed and first click always give the same board
+            let mut again = Game::new(16, 16, 40, Some(seed));
+            again.no_guess = true;
+            again.left_click((8, 8));
+            assert_eq!(game.mine_positions(), again.mine_positions(), "seed {seed}");
+
+            while game.game_state == GameState::DuringGame {
+                let safe = solver::solve(&game.get_all_images(), game.total_mines)
+                    .into_iter()
+                    .filter(|finding| finding.deduction == solver::Deduction::Safe)
+                    .collect::<Vec<_>>();
+                assert!(!safe.is_empty(), "seed {seed} needs a guess");
+                // Earlier clicks may have revealed a cell already and clicking it would chord
+                for finding in safe {
+                    if game.get_image_at(finding.pos) == CellImage::Hidden {
+                        game.left_click(finding.pos);
+                    }
+                }
+            }
+            assert_eq!(game.game_state, GameState::Victory, "seed {seed}");
+        }
+    }
+}
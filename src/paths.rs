@@ -0,0 +1,38 @@
+use std::{
+    env,
+    path::PathBuf,
+};
+
+/// Name of the directory holding minesweeper's files inside the platform's directories.
+const APP_DIR_NAME: &str = "minesweeper";
+
+/// Returns the directory minesweeper should store data such as replays in, following the platform's
+/// conventions. Returns [None] if the platform's directories can't be found.
+pub fn data_dir() -> Option<PathBuf> {
+    platform_dir("XDG_DATA_HOME", &[".local", "share"])
+}
+
+/// Returns the directory minesweeper should store configuration and records in, following the
+/// platform's conventions. Returns [None] if the platform's directories can't be found.
+pub fn config_dir() -> Option<PathBuf> {
+    platform_dir("XDG_CONFIG_HOME", &[".config"])
+}
+
+/// Returns the app's directory inside the platform directory given by the XDG variable or its
+/// default under the home directory. Windows and macOS use a single directory for both.
+fn platform_dir(xdg_var: &str, home_default: &[&str]) -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        PathBuf::from(env::var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(env::var_os("HOME")?).join("Library/Application Support")
+    } else if let Some(dir) = env::var_os(xdg_var).filter(|dir| !dir.is_empty()) {
+        PathBuf::from(dir)
+    } else {
+        home_default
+            .iter()
+            .fold(PathBuf::from(env::var_os("HOME")?), |path, dir| {
+                path.join(dir)
+            })
+    };
+    Some(base.join(APP_DIR_NAME))
+}
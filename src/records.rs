@@ -0,0 +1,395 @@
+use crate::{
+    minesweeper::{
+        Count,
+        Dim,
+        Game,
+    },
+    paths,
+};
+use anyhow::{
+    Context,
+    Result,
+    bail,
+    ensure,
+};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs,
+    io,
+    path::PathBuf,
+    str::FromStr,
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
+
+/// First line of every records file, used to recognize the format.
+const RECORDS_HEADER: &str = "minesweeper records 1";
+/// Name of the records file inside the config directory.
+const RECORDS_FILE_NAME: &str = "records.txt";
+/// Number of best times kept for each difficulty.
+pub const MAX_RECORDS_PER_DIFFICULTY: usize = 10;
+
+/// The board parameters records are kept separately for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Difficulty {
+    pub width: Dim,
+    pub height: Dim,
+    pub mines: Count,
+}
+
+impl Difficulty {
+    /// Returns the [Difficulty] of the given game.
+    pub fn of(game: &Game) -> Self {
+        Self {
+            width: game.width,
+            height: game.height,
+            mines: game.total_mines,
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}/{}", self.width, self.height, self.mines)
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (size, mines) = s.split_once('/').context("missing mine count")?;
+        let (width, height) = size.split_once('x').context("missing height")?;
+        Ok(Self {
+            width: width.parse().context("invalid width")?,
+            height: height.parse().context("invalid height")?,
+            mines: mines.parse().context("invalid mine count")?,
+        })
+    }
+}
+
+/// A won game worth remembering.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Record {
+    /// How long the game took.
+    pub time: Duration,
+    /// When the game was won, in seconds since the Unix epoch.
+    pub date: u64,
+    /// The 3BV of the board, i.e. how many clicks it takes at the least.
+    pub three_bv: u32,
+    /// Seed the board was generated with.
+    pub seed: u64,
+}
+
+impl Record {
+    /// Creates a [Record] of a game won just now.
+    pub fn new(time: Duration, three_bv: u32, seed: u64) -> Self {
+        let date = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self {
+            time,
+            date,
+            three_bv,
+            seed,
+        }
+    }
+}
+
+/// The best times for every difficulty that has been won, fastest first.
+#[derive(Debug, Default)]
+pub struct Records {
+    by_difficulty: BTreeMap<Difficulty, Vec<Record>>,
+}
+
+impl Records {
+    /// Returns the path of the records file.
+    pub fn path() -> Result<PathBuf> {
+        Ok(paths::config_dir()
+            .context("Could not find a directory to store records in")?
+            .join(RECORDS_FILE_NAME))
+    }
+
+    /// Loads the records from the records file, or returns no records if it doesn't exist yet.
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        match fs::read_to_string(&path) {
+            Ok(contents) => contents
+                .parse()
+                .with_context(|| format!("Invalid records file {}", path.display())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| format!("Could not read {}", path.display())),
+        }
+    }
+
+    /// Writes the records to the records file.
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Could not create directory {}", dir.display()))?;
+        }
+        fs::write(&path, self.to_string())
+            .with_context(|| format!("Could not write records to {}", path.display()))
+    }
+
+    /// Adds the record if it is among the best for its difficulty, dropping the slowest one if
+    /// there are too many. Returns the 1-based rank of the record if it was added.
+    pub fn insert(&mut self, difficulty: Difficulty, record: Record) -> Option<usize> {
+        let records = self.by_difficulty.entry(difficulty).or_default();
+        // Ties go to the older record
+        let rank = records.partition_point(|other| other.time <= record.time);
+        if rank >= MAX_RECORDS_PER_DIFFICULTY {
+            return None;
+        }
+        records.insert(rank, record);
+        records.truncate(MAX_RECORDS_PER_DIFFICULTY);
+        Some(rank + 1)
+    }
+
+    /// Returns the records as a human readable table, grouped by difficulty.
+    pub fn table(&self) -> String {
+        if self.by_difficulty.is_empty() {
+            return "No records yet, win a game without hints or practice mode to set one".into();
+        }
+        let mut table = String::new();
+        for (difficulty, records) in &self.by_difficulty {
+            if !table.is_empty() {
+                table.push('\n');
+            }
+            table += &format!("{difficulty}\n");
+            table += &format!(
+                "{:>4}  {:>9}  {:<10}  {:>5}  {}\n",
+                "Rank", "Time", "Date", "3BV", "Seed"
+            );
+            for (rank, record) in records.iter().enumerate() {
+                table += &format!(
+                    "{:>4}  {:>9.3}  {:<10}  {:>5}  {}\n",
+                    rank + 1,
+                    record.time.as_secs_f64(),
+                    format_date(record.date),
+                    record.three_bv,
+                    record.seed
+                );
+            }
+        }
+        table.pop();
+        table
+    }
+}
+
+/// Writes the records in the format of the records file: a header followed by one line per
+/// record of its difficulty, time in seconds, date, 3BV, and seed.
+impl fmt::Display for Records {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{RECORDS_HEADER}")?;
+        for (difficulty, records) in &self.by_difficulty {
+            for record in records {
+                writeln!(
+                    f,
+                    "{difficulty} {:.3} {} {} {}",
+                    record.time.as_secs_f64(),
+                    record.date,
+                    record.three_bv,
+                    record.seed
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Records {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut lines = s.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+        ensure!(
+            lines.next().map(|(_, line)| line) == Some(RECORDS_HEADER),
+            "not a records file"
+        );
+
+        let mut records = Self::default();
+        for (line_number, line) in lines.filter(|(_, line)| !line.is_empty()) {
+            let parse = || -> Result<(Difficulty, Record)> {
+                let fields = line.split_whitespace().collect::<Vec<_>>();
+                let [difficulty, time, date, three_bv, seed] = fields[..] else {
+                    bail!("expected difficulty, time, date, 3BV, and seed");
+                };
+                let time = time
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|time| Duration::try_from_secs_f64(time).ok())
+                    .context("invalid time")?;
+                let record = Record {
+                    time,
+                    date: date.parse().context("invalid date")?,
+                    three_bv: three_bv.parse().context("invalid 3BV")?,
+                    seed: seed.parse().context("invalid seed")?,
+                };
+                Ok((difficulty.parse()?, record))
+            };
+            let (difficulty, record) = parse().with_context(|| format!("line {line_number}"))?;
+            records.insert(difficulty, record);
+        }
+        Ok(records)
+    }
+}
+
+/// Whether the given game can set a record if won. Games using hints or practice mode don't count.
+pub fn counts(game: &Game) -> bool {
+    !game.practice && !game.is_hinted()
+}
+
+/// Formats seconds since the Unix epoch as a UTC date in the form YYYY-MM-DD.
+fn format_date(secs: u64) -> String {
+    // Converts days since the epoch to a civil date, see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPERT: Difficulty = Difficulty {
+        width: 30,
+        height: 16,
+        mines: 99,
+    };
+
+    /// Creates a record with the given time in milliseconds and the time as its seed, so records
+    /// can be told apart.
+    fn record(millis: u64) -> Record {
+        Record {
+            time: Duration::from_millis(millis),
+            date: 1_700_000_000,
+            three_bv: 120,
+            seed: millis,
+        }
+    }
+
+    /// Returns the seeds of the records kept for the given difficulty, fastest first.
+    fn seeds(records: &Records, difficulty: Difficulty) -> Vec<u64> {
+        records.by_difficulty[&difficulty]
+            .iter()
+            .map(|record| record.seed)
+            .collect()
+    }
+
+    #[test]
+    fn insert() {
+        let mut records = Records::default();
+        assert_eq!(records.insert(EXPERT, record(3000)), Some(1));
+        assert_eq!(records.insert(EXPERT, record(1000)), Some(1));
+        assert_eq!(records.insert(EXPERT, record(2000)), Some(2));
+        assert_eq!(seeds(&records, EXPERT), [1000, 2000, 3000]);
+
+        // Ties go to the older record
+        let tie = Record {
+            seed: 42,
+            ..record(2000)
+        };
+        assert_eq!(records.insert(EXPERT, tie), Some(3));
+        assert_eq!(seeds(&records, EXPERT), [1000, 2000, 42, 3000]);
+
+        // Other difficulties are ranked separately
+        let beginner = Difficulty {
+            width: 9,
+            height: 9,
+            mines: 10,
+        };
+        assert_eq!(records.insert(beginner, record(5000)), Some(1));
+        assert_eq!(seeds(&records, EXPERT).len(), 4);
+    }
+
+    #[test]
+    fn insert_keeps_the_best() {
+        let mut records = Records::default();
+        for millis in (1..=MAX_RECORDS_PER_DIFFICULTY as u64).map(|n| n * 1000) {
+            records.insert(EXPERT, record(millis));
+        }
+        let slowest = MAX_RECORDS_PER_DIFFICULTY as u64 * 1000;
+        assert_eq!(records.insert(EXPERT, record(slowest + 1)), None);
+        assert_eq!(records.insert(EXPERT, record(slowest)), None);
+        assert_eq!(records.insert(EXPERT, record(5500)), Some(6));
+        let seeds = seeds(&records, EXPERT);
+        assert_eq!(seeds.len(), MAX_RECORDS_PER_DIFFICULTY);
+        assert_eq!(seeds[5], 5500);
+        assert_eq!(*seeds.last().unwrap(), slowest - 1000);
+    }
+
+    #[test]
+    fn practice_and_hinted_games_dont_count() {
+        let game = Game::new(9, 9, 10, Some(0));
+        assert!(counts(&game));
+        let mut practice = Game::new(9, 9, 10, Some(0));
+        practice.practice = true;
+        assert!(!counts(&practice));
+        let mut hinted = Game::new(9, 9, 10, Some(0));
+        hinted.hints = 1;
+        assert!(!counts(&hinted));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut records = Records::default();
+        records.insert(EXPERT, record(61_234));
+        records.insert(
+            Difficulty {
+                width: 9,
+                height: 9,
+                mines: 10,
+            },
+            record(7_500),
+        );
+        let text = records.to_string();
+        assert_eq!(
+            text,
+            "minesweeper records 1\n\
+             9x9/10 7.500 1700000000 120 7500\n\
+             30x16/99 61.234 1700000000 120 61234\n"
+        );
+        let parsed: Records = text.parse().unwrap();
+        assert_eq!(parsed.by_difficulty, records.by_difficulty);
+
+        assert!("".parse::<Records>().is_err());
+        assert!("minesweeper records 2\n".parse::<Records>().is_err());
+        for line in [
+            "30x16 1.000 0 1 1",
+            "30x16/99 -1 0 1 1",
+            "30x16/99 1.000 0 1",
+            "30x16/99 1.000 0 1 x",
+        ] {
+            let text = format!("{RECORDS_HEADER}\n{line}\n");
+            assert!(text.parse::<Records>().is_err(), "{line}");
+        }
+    }
+
+    #[test]
+    fn format_date() {
+        assert_eq!(super::format_date(0), "1970-01-01");
+        assert_eq!(super::format_date(86_399), "1970-01-01");
+        assert_eq!(super::format_date(951_782_400), "2000-02-29");
+        assert_eq!(super::format_date(1_700_000_000), "2023-11-14");
+        assert_eq!(super::format_date(4_107_542_400), "2100-03-01");
+    }
+}
@@ -0,0 +1,487 @@
+use crate::{
+    minesweeper::{
+        Count,
+        Dim,
+        Game,
+        Pos,
+    },
+    paths,
+};
+use anyhow::{
+    Context,
+    Result,
+    anyhow,
+    bail,
+    ensure,
+};
+use std::{
+    fmt,
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+    str::FromStr,
+    time::{
+        Duration,
+        Instant,
+        SystemTime,
+    },
+};
+
+/// First line of every replay file. Bumped whenever the format changes.
+const REPLAY_HEADER: &str = "minesweeper replay 1";
+/// Extension given to saved replay files.
+const REPLAY_EXTENSION: &str = "replay";
+/// Slowest and fastest speeds a replay can be played back at.
+pub const MIN_SPEED: f32 = 0.25;
+pub const MAX_SPEED: f32 = 8.0;
+
+/// An input made by the player while recording.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// The cursor moved to the given position, relative to the game with both axes from -1 to 1.
+    CursorMoved([f32; 2]),
+    LeftPressed,
+    /// The left button was released over a cell that wasn't shown.
+    LeftClick(Pos),
+    /// The left button was released over a shown cell, revealing the cells around it.
+    Chord(Pos),
+    /// The left button was released somewhere other than the grid.
+    LeftReleased,
+    RightClick(Pos),
+    Undo,
+    Redo,
+}
+
+impl FromStr for Event {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut words = s.split_whitespace();
+        let name = words.next().context("missing event")?;
+        let args: Vec<&str> = words.collect();
+        let pos = || -> Result<Pos> {
+            match args[..] {
+                [row, col] => Ok((row.parse()?, col.parse()?)),
+                _ => bail!("event {name} needs a row and a column"),
+            }
+        };
+        let no_args = |event| -> Result<Event> {
+            ensure!(args.is_empty(), "event {name} takes no arguments");
+            Ok(event)
+        };
+        match name {
+            "move" => match args[..] {
+                [x, y] => Ok(Event::CursorMoved([x.parse()?, y.parse()?])),
+                _ => bail!("event move needs an x and a y"),
+            },
+            "left_press" => no_args(Event::LeftPressed),
+            "left_click" => Ok(Event::LeftClick(pos()?)),
+            "chord" => Ok(Event::Chord(pos()?)),
+            "left_release" => no_args(Event::LeftReleased),
+            "right_click" => Ok(Event::RightClick(pos()?)),
+            "undo" => no_args(Event::Undo),
+            "redo" => no_args(Event::Redo),
+            _ => bail!("unknown event: {name}"),
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::CursorMoved([x, y]) => write!(f, "move {x} {y}"),
+            Event::LeftPressed => write!(f, "left_press"),
+            Event::LeftClick((row, col)) => write!(f, "left_click {row} {col}"),
+            Event::Chord((row, col)) => write!(f, "chord {row} {col}"),
+            Event::LeftReleased => write!(f, "left_release"),
+            Event::RightClick((row, col)) => write!(f, "right_click {row} {col}"),
+            Event::Undo => write!(f, "undo"),
+            Event::Redo => write!(f, "redo"),
+        }
+    }
+}
+
+/// A recorded game: the layout of the board and every input made, timestamped from when the board
+/// was last reset.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub width: Dim,
+    pub height: Dim,
+    pub seed: u64,
+    /// Whether left clicking a flag question marked it, which changes what the inputs do.
+    pub question_marks: bool,
+    /// Whether moves could be undone and redone, so the recorded undos and redos did something.
+    pub practice: bool,
+    pub first_click: Pos,
+    pub mines: Vec<Pos>,
+    pub events: Vec<(Duration, Event)>,
+}
+
+impl Replay {
+    /// Loads the replay saved in the given file.
+    pub fn load(path: &Path) -> Result<Self> {
+        fs::read_to_string(path)
+            .with_context(|| format!("Could not read replay {}", path.display()))?
+            .parse()
+            .with_context(|| format!("Invalid replay {}", path.display()))
+    }
+
+    /// Creates a [Game] with the recorded layout that starts the same way the recorded game did.
+    pub fn game(&self) -> Result<Game> {
+        let num_cells = self.width as usize * self.height as usize;
+        ensure!(
+            self.width != 0 && self.height != 0 && !self.mines.is_empty(),
+            "replay has an empty board"
+        );
+        ensure!(self.mines.len() < num_cells, "replay has too many mines");
+        let mut game = Game::new(
+            self.width,
+            self.height,
+            self.mines.len() as Count,
+            Some(self.seed),
+        );
+        game.set_layout(&self.mines);
+        game.question_marks = self.question_marks;
+        game.practice = self.practice;
+        Ok(game)
+    }
+
+    /// Saves the replay to a new file in the replays directory. Returns the path of the file.
+    pub fn save(&self) -> Result<PathBuf> {
+        let dir = paths::data_dir()
+            .context("Could not find a directory to save replays in")?
+            .join("replays");
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Could not create directory {}", dir.display()))?;
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_millis();
+        let path = dir.join(format!("{timestamp}-{}.{REPLAY_EXTENSION}", self.seed));
+        fs::write(&path, self.to_string())
+            .with_context(|| format!("Could not write replay to {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{REPLAY_HEADER}")?;
+        writeln!(f, "width {}", self.width)?;
+        writeln!(f, "height {}", self.height)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "question_marks {}", self.question_marks)?;
+        writeln!(f, "practice {}", self.practice)?;
+        writeln!(
+            f,
+            "first_click {} {}",
+            self.first_click.0, self.first_click.1
+        )?;
+        for (row, col) in self.mines.iter() {
+            writeln!(f, "mine {row} {col}")?;
+        }
+        writeln!(f, "events")?;
+        for (time, event) in self.events.iter() {
+            writeln!(f, "{:.3} {event}", time.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.trim()));
+        let (_, header) = lines.next().context("empty replay")?;
+        ensure!(
+            header == REPLAY_HEADER,
+            "not a replay or unsupported version: {header}"
+        );
+
+        let mut width = None;
+        let mut height = None;
+        let mut seed = None;
+        let mut question_marks = true;
+        let mut practice = false;
+        let mut first_click = None;
+        let mut mines = Vec::new();
+        let mut events = Vec::new();
+        let mut in_events = false;
+        for (line_num, line) in lines {
+            if line.is_empty() {
+                continue;
+            }
+            let result = if in_events {
+                line.split_once(' ')
+                    .context("expected a time and an event")
+                    .and_then(|(time, event)| {
+                        let time = time
+                            .parse::<f64>()
+                            .ok()
+                            .and_then(|time| Duration::try_from_secs_f64(time).ok())
+                            .with_context(|| format!("invalid time: {time}"))?;
+                        events.push((time, event.parse()?));
+                        Ok(())
+                    })
+            } else if line == "events" {
+                in_events = true;
+                Ok(())
+            } else {
+                let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+                let pos = || -> Result<Pos> {
+                    let (row, col) = value
+                        .split_once(' ')
+                        .with_context(|| format!("{key} needs a row and a column"))?;
+                    Ok((row.parse()?, col.parse()?))
+                };
+                match key {
+                    "width" => value
+                        .parse()
+                        .map(|value| width = Some(value))
+                        .map_err(Into::into),
+                    "height" => value
+                        .parse()
+                        .map(|value| height = Some(value))
+                        .map_err(Into::into),
+                    "seed" => value
+                        .parse()
+                        .map(|value| seed = Some(value))
+                        .map_err(Into::into),
+                    "question_marks" => value
+                        .parse()
+                        .map(|value| question_marks = value)
+                        .map_err(Into::into),
+                    "practice" => value
+                        .parse()
+                        .map(|value| practice = value)
+                        .map_err(Into::into),
+                    "first_click" => pos().map(|pos| first_click = Some(pos)),
+                    "mine" => pos().map(|pos| mines.push(pos)),
+                    _ => Err(anyhow!("unknown key: {key}")),
+                }
+            };
+            result.with_context(|| format!("line {line_num}"))?;
+        }
+
+        let replay = Replay {
+            width: width.context("missing width")?,
+            height: height.context("missing height")?,
+            seed: seed.context("missing seed")?,
+            question_marks,
+            practice,
+            first_click: first_click.context("missing first_click")?,
+            mines,
+            events,
+        };
+        let in_bounds = |(row, col): &Pos| *row < replay.height && *col < replay.width;
+        ensure!(
+            replay.mines.iter().all(in_bounds) && in_bounds(&replay.first_click),
+            "position outside of the board"
+        );
+        Ok(replay)
+    }
+}
+
+/// Records the inputs made during a game so they can be saved as a [Replay] once it ends.
+pub struct Recorder {
+    start: Instant,
+    events: Vec<(Duration, Event)>,
+}
+
+impl Recorder {
+    /// Starts a new recording with no events, timestamping from now.
+    pub fn start() -> Self {
+        Self {
+            start: Instant::now(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Records the given event as happening now.
+    pub fn record(&mut self, event: Event) {
+        self.events.push((self.start.elapsed(), event));
+    }
+
+    /// Returns a [Replay] of the given game using the events recorded so far. Returns [None] if
+    /// the game hasn't started.
+    pub fn replay(&self, game: &Game) -> Option<Replay> {
+        Some(Replay {
+            width: game.width,
+            height: game.height,
+            seed: game.seed,
+            question_marks: game.question_marks,
+            practice: game.practice,
+            first_click: game.first_click()?,
+            mines: game.mine_positions(),
+            events: self.events.clone(),
+        })
+    }
+}
+
+/// Plays back a [Replay] in time, handing out its events as they become due.
+pub struct Playback {
+    replay: Replay,
+    /// Index of the next event to hand out.
+    next: usize,
+    /// How far into the replay playback is.
+    position: Duration,
+    speed: f32,
+    playing: bool,
+    /// When `position` was last brought up to date.
+    last_update: Instant,
+    /// When the recorded game started and ended, once playback reaches those points.
+    pub game_start: Option<Duration>,
+    pub game_end: Option<Duration>,
+}
+
+impl Playback {
+    /// Creates a new [Playback] of the given replay, playing from the start at normal speed.
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next: 0,
+            position: Duration::ZERO,
+            speed: 1.0,
+            playing: true,
+            last_update: Instant::now(),
+            game_start: None,
+            game_end: None,
+        }
+    }
+
+    /// Returns how far into the replay playback is.
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    /// Returns the time of the last event in the replay.
+    pub fn duration(&self) -> Duration {
+        self.replay
+            .events
+            .last()
+            .map_or(Duration::ZERO, |(time, _)| *time)
+    }
+
+    /// Returns how long the recorded game had been running at the current position.
+    pub fn game_time(&self) -> Duration {
+        let end = self.game_end.unwrap_or(self.position);
+        self.game_start
+            .map_or(Duration::ZERO, |start| end.saturating_sub(start))
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Whether every event has been handed out.
+    pub fn is_finished(&self) -> bool {
+        self.next == self.replay.events.len()
+    }
+
+    /// Pauses playback if it is playing and resumes it otherwise. Resuming a finished playback
+    /// does nothing.
+    pub fn toggle_playing(&mut self) {
+        self.update_position();
+        self.playing = !self.playing && !self.is_finished();
+    }
+
+    /// Doubles the speed if `faster` and halves it otherwise, within [MIN_SPEED] and [MAX_SPEED].
+    pub fn change_speed(&mut self, faster: bool) {
+        self.update_position();
+        let speed = if faster {
+            self.speed * 2.0
+        } else {
+            self.speed / 2.0
+        };
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    /// Brings the position up to date and returns every event that became due, with its time.
+    /// Pauses once the last event is handed out.
+    pub fn due_events(&mut self) -> Vec<(Duration, Event)> {
+        self.update_position();
+        let mut result = Vec::new();
+        while let Some((time, event)) = self.replay.events.get(self.next)
+            && *time <= self.position
+        {
+            result.push((*time, *event));
+            self.next += 1;
+        }
+        if self.is_finished() {
+            self.playing = false;
+        }
+        result
+    }
+
+    /// Pauses playback and returns the next event, moving the position up to it.
+    pub fn step(&mut self) -> Option<(Duration, Event)> {
+        self.playing = false;
+        let (time, event) = *self.replay.events.get(self.next)?;
+        self.next += 1;
+        self.position = self.position.max(time);
+        Some((time, event))
+    }
+
+    /// Moves playback to the given position, clamped to the replay. Since events can't be undone,
+    /// returns every event from the start of the replay up to the position so they can be
+    /// performed on a fresh game.
+    pub fn seek(&mut self, position: Duration) -> Vec<(Duration, Event)> {
+        self.position = position.min(self.duration());
+        self.last_update = Instant::now();
+        self.next = 0;
+        self.game_start = None;
+        self.game_end = None;
+        self.due_events()
+    }
+
+    /// Returns when the next event is due, or [None] if playback is paused or finished.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        if !self.playing {
+            return None;
+        }
+        let (time, _) = self.replay.events.get(self.next)?;
+        let delay = time.saturating_sub(self.position).div_f32(self.speed);
+        Some(self.last_update + delay)
+    }
+
+    /// Advances the position by the time passed since the last update, scaled by the speed.
+    fn update_position(&mut self) {
+        let now = Instant::now();
+        if self.playing {
+            self.position += (now - self.last_update).mul_f32(self.speed);
+        }
+        self.last_update = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn practice_comes_from_the_header() {
+        let replay: Replay = "minesweeper replay 1\nwidth 9\nheight 9\nseed 1\nfirst_click 0 0\n\
+            mine 4 4\nevents\n0.5 left_click 0 0\n1.0 undo\n"
+            .parse()
+            .unwrap();
+        assert!(!replay.practice);
+        assert!(!replay.game().unwrap().practice);
+
+        let replay = Replay {
+            practice: true,
+            ..replay
+        };
+        let replay: Replay = replay.to_string().parse().unwrap();
+        assert!(replay.practice);
+        assert!(replay.game().unwrap().practice);
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{
+    minesweeper::Game,
+    paths,
+};
+use anyhow::{
+    Context,
+    Result,
+};
+use std::{
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::Duration,
+};
+
+/// Name of the file games are saved to when quitting.
+const SAVE_FILE_NAME: &str = "saved_game.txt";
+
+/// Returns the path games are saved to when quitting and resumed from with `--resume`.
+pub fn default_path() -> Result<PathBuf> {
+    Ok(paths::data_dir()
+        .context("Could not find a directory to save games in")?
+        .join(SAVE_FILE_NAME))
+}
+
+/// Saves the given game and how long it has been running to the given file.
+pub fn save(game: &Game, elapsed: Duration, path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Could not create directory {}", dir.display()))?;
+    }
+    let contents = format!(
+        "elapsed {:.3}\n{}",
+        elapsed.as_secs_f64(),
+        game.to_save_string()
+    );
+    fs::write(path, contents)
+        .with_context(|| format!("Could not write saved game to {}", path.display()))
+}
+
+/// Loads a game and how long it had been running from the given file.
+pub fn load(path: &Path) -> Result<(Game, Duration)> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Could not read saved game {}", path.display()))?;
+    let parse = || -> Result<(Game, Duration)> {
+        let (elapsed, game) = contents.split_once('\n').context("empty saved game")?;
+        let elapsed = elapsed
+            .strip_prefix("elapsed ")
+            .and_then(|elapsed| elapsed.trim().parse::<f64>().ok())
+            .and_then(|elapsed| Duration::try_from_secs_f64(elapsed).ok())
+            .context("missing or invalid elapsed time")?;
+        Ok((Game::from_save_str(game)?, elapsed))
+    };
+    parse().with_context(|| format!("Invalid saved game {}", path.display()))
+}
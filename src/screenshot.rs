@@ -0,0 +1,235 @@
+use crate::{
+    main_window_graphics::{
+        self,
+        Display,
+        MainWindowGraphics,
+        Skin,
+    },
+    minesweeper::Game,
+};
+use anyhow::{
+    Context,
+    Result,
+};
+use pollster::FutureExt;
+use std::{
+    path::Path,
+    time::Duration,
+};
+
+/// Renders the given game as it would look in the window after running for the given time, drawn
+/// with the given [Skin] and with each texture pixel taking up the given number of pixels. Needs no
+/// window and prefers a software adapter, drawing on the CPU if there is no adapter at all, so it
+/// works without a GPU.
+pub fn render(game: &Game, elapsed: Duration, scale: u32, skin: Skin) -> Result<image::RgbaImage> {
+    let native_size = main_window_graphics::get_native_size(game.width, game.height);
+    let size = winit::dpi::PhysicalSize::new(native_size.width * scale, native_size.height * scale);
+    match request_device() {
+        Ok((device, queue)) => {
+            let mut graphics = MainWindowGraphics::new(
+                &device,
+                &queue,
+                main_window_graphics::OFFSCREEN_FORMAT,
+                game,
+                skin,
+            );
+            show_game(&mut graphics, game, elapsed);
+            graphics.render_to_image(&device, &queue, size)
+        }
+        Err(err) => {
+            log::warn!("{err:#}, drawing on the CPU instead");
+            let mut graphics = MainWindowGraphics::without_gpu(game, skin);
+            show_game(&mut graphics, game, elapsed);
+            Ok(graphics.rasterize(size))
+        }
+    }
+}
+
+/// Updates the parts of the graphics that the window keeps up to date itself rather than taking
+/// from the game when they are created.
+fn show_game(graphics: &mut MainWindowGraphics, game: &Game, elapsed: Duration) {
+    graphics.update_display(Display::Timer, elapsed.as_secs() as i32);
+    graphics.update_face(main_window_graphics::face_from_game_state(
+        false,
+        false,
+        &game.game_state,
+    ));
+}
+
+/// Renders the given game like [render] and writes it to the given PNG file.
+pub fn save(game: &Game, elapsed: Duration, scale: u32, skin: Skin, path: &Path) -> Result<()> {
+    render(game, elapsed, scale, skin)?
+        .save_with_format(path, image::ImageFormat::Png)
+        .with_context(|| format!("Could not write screenshot to {}", path.display()))
+}
+
+/// Creates a device without a surface. Uses the fallback adapter, like a software renderer, when
+/// there is one so images don't depend on the GPU, and any other adapter otherwise.
+fn request_device() -> Result<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+    let request_adapter = |force_fallback_adapter| {
+        instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter,
+            })
+            .block_on()
+    };
+    let adapter = request_adapter(true)
+        .or_else(|_| request_adapter(false))
+        .context("Could not find an adapter to render with")?;
+    log::info!("Rendering with {:?}", adapter.get_info());
+    adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::downlevel_defaults(),
+            label: None,
+            memory_hints: Default::default(),
+            trace: wgpu::Trace::Off,
+        })
+        .block_on()
+        .context("Could not create a device to render with")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Directory holding the board the tests render and its expected image.
+    const TEST_DATA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/screenshot");
+
+    /// Loads the game part way through, with an opening, numbers, and flags, that the tests render
+    /// along with how long it has been running.
+    fn board() -> (Game, Duration) {
+        crate::save::load(&Path::new(TEST_DATA_DIR).join("board.txt")).unwrap()
+    }
+
+    /// Creates the device used to test rendering on the GPU, or [None] to skip those checks if
+    /// there is no adapter.
+    fn test_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+        request_device()
+            .inspect_err(|err| eprintln!("Skipping test: {err:#}"))
+            .ok()
+    }
+
+    /// Returns the size of the given game's window with each texture pixel taking up the given
+    /// number of pixels.
+    fn scaled_size(game: &Game, scale: u32) -> winit::dpi::PhysicalSize<u32> {
+        let native_size = main_window_graphics::get_native_size(game.width, game.height);
+        winit::dpi::PhysicalSize::new(native_size.width * scale, native_size.height * scale)
+    }
+
+    /// Renders the given game on the given device like [render].
+    fn render_on_gpu(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        game: &Game,
+        elapsed: Duration,
+        scale: u32,
+    ) -> image::RgbaImage {
+        let mut graphics = MainWindowGraphics::new(
+            device,
+            queue,
+            main_window_graphics::OFFSCREEN_FORMAT,
+            game,
+            Skin::classic(),
+        );
+        show_game(&mut graphics, game, elapsed);
+        graphics
+            .render_to_image(device, queue, scaled_size(game, scale))
+            .unwrap()
+    }
+
+    #[test]
+    fn matches_golden_image() {
+        let (game, elapsed) = board();
+        let golden = image::open(Path::new(TEST_DATA_DIR).join("board.png"))
+            .unwrap()
+            .to_rgba8();
+        let out_of_date = "board.txt no longer renders as board.png, take a new --screenshot at \
+                           --scale 2 if the change is intended";
+
+        // The rasterizer needs no adapter, so the image is always checked
+        let mut graphics = MainWindowGraphics::without_gpu(&game, Skin::classic());
+        show_game(&mut graphics, &game, elapsed);
+        assert!(
+            graphics.rasterize(scaled_size(&game, 2)) == golden,
+            "{out_of_date}"
+        );
+
+        if let Some((device, queue)) = test_device() {
+            let rendered = render_on_gpu(&device, &queue, &game, elapsed, 2);
+            assert!(rendered == golden, "{out_of_date}");
+        }
+    }
+
+    #[test]
+    fn rasterizer_matches_gpu() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let (game, elapsed) = board();
+        for scale in [1, 3] {
+            let gpu = render_on_gpu(&device, &queue, &game, elapsed, scale);
+            let mut graphics = MainWindowGraphics::without_gpu(&game, Skin::classic());
+            show_game(&mut graphics, &game, elapsed);
+            let cpu = graphics.rasterize(scaled_size(&game, scale));
+            assert_eq!(gpu.dimensions(), scaled_size(&game, scale).into());
+            assert!(
+                gpu == cpu,
+                "rasterizer differs from the GPU at scale {scale}"
+            );
+        }
+    }
+
+    #[test]
+    fn rasterizer_matches_gpu_tints() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let (game, elapsed) = board();
+        let size = scaled_size(&game, 2);
+
+        // Every cell gets a different probability so tints cover many colors
+        let cells = game.width as usize * game.height as usize;
+        let probabilities: Vec<Vec<Option<f64>>> = (0..game.height as usize)
+            .map(|row| {
+                (0..game.width as usize)
+                    .map(|col| Some((row * game.width as usize + col) as f64 / (cells - 1) as f64))
+                    .collect()
+            })
+            .collect();
+        let tint = |graphics: &mut MainWindowGraphics| {
+            show_game(graphics, &game, elapsed);
+            graphics.show_probabilities(&probabilities);
+            graphics.highlight_cell((3, 4));
+            graphics.highlight_display(Display::Timer);
+        };
+        let mut graphics = MainWindowGraphics::new(
+            &device,
+            &queue,
+            main_window_graphics::OFFSCREEN_FORMAT,
+            &game,
+            Skin::classic(),
+        );
+        tint(&mut graphics);
+        let gpu = graphics.render_to_image(&device, &queue, size).unwrap();
+        let mut graphics = MainWindowGraphics::without_gpu(&game, Skin::classic());
+        let untinted = graphics.rasterize(size);
+        tint(&mut graphics);
+        let cpu = graphics.rasterize(size);
+
+        for ((gpu, cpu), untinted) in gpu.pixels().zip(cpu.pixels()).zip(untinted.pixels()) {
+            if cpu == untinted {
+                assert_eq!(gpu, cpu);
+            } else {
+                let off_by = gpu.0.iter().zip(cpu.0).map(|(gpu, cpu)| gpu.abs_diff(cpu));
+                assert!(off_by.max().unwrap() <= 1, "{gpu:?} != {cpu:?}");
+            }
+        }
+    }
+}
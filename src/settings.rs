@@ -0,0 +1,187 @@
+use crate::{
+    main_window_graphics::MIN_GRID_WIDTH,
+    minesweeper::{
+        Count,
+        Dim,
+        Game,
+    },
+};
+
+/// Largest value the seven-segment displays of the settings window can show.
+const MAX_DISPLAYED_VALUE: u32 = 999;
+
+/// A number edited with a seven-segment display in the settings window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Width,
+    Height,
+    Mines,
+    /// Percent of the cells that are mines. Editing it changes the number of mines.
+    PercentMines,
+}
+
+/// A setting that is either on or off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Toggle {
+    QuestionMarks,
+    NoGuess,
+    Practice,
+}
+
+/// Anything in the settings window that can be clicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Field(Field),
+    Toggle(Toggle),
+    Apply,
+    Cancel,
+}
+
+impl Control {
+    /// Every [Control] in the order they are laid out, top to bottom.
+    pub const ALL: [Control; 9] = [
+        Control::Field(Field::Width),
+        Control::Field(Field::Height),
+        Control::Field(Field::Mines),
+        Control::Field(Field::PercentMines),
+        Control::Toggle(Toggle::QuestionMarks),
+        Control::Toggle(Toggle::NoGuess),
+        Control::Toggle(Toggle::Practice),
+        Control::Apply,
+        Control::Cancel,
+    ];
+
+    /// Returns a description of the [Control] shown in the window title when it is hovered, since
+    /// the atlas has no text to label it with.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Control::Field(Field::Width) => "Width",
+            Control::Field(Field::Height) => "Height",
+            Control::Field(Field::Mines) => "Mines",
+            Control::Field(Field::PercentMines) => "Percent mines",
+            Control::Toggle(Toggle::QuestionMarks) => "Question marks",
+            Control::Toggle(Toggle::NoGuess) => "No guessing",
+            Control::Toggle(Toggle::Practice) => "Practice mode (undo and redo)",
+            Control::Apply => "Apply and start a new game",
+            Control::Cancel => "Cancel",
+        }
+    }
+}
+
+/// The settings being edited in the settings window. Values are kept valid while editing, so they
+/// can always be applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub width: Dim,
+    pub height: Dim,
+    pub mines: Count,
+    pub question_marks: bool,
+    pub no_guess: bool,
+    pub practice: bool,
+}
+
+impl Settings {
+    /// Returns the current settings of the given game. Games started from the command line can
+    /// have more mines than the display can show, so those are lowered to the most it can.
+    pub fn of(game: &Game) -> Self {
+        Self {
+            width: game.width,
+            height: game.height,
+            mines: game.total_mines.min(MAX_DISPLAYED_VALUE as Count),
+            question_marks: game.question_marks,
+            no_guess: game.no_guess,
+            practice: game.practice,
+        }
+    }
+
+    /// Returns the value of the given [Field].
+    pub fn get(&self, field: Field) -> u32 {
+        match field {
+            Field::Width => self.width as u32,
+            Field::Height => self.height as u32,
+            Field::Mines => self.mines as u32,
+            Field::PercentMines => {
+                (self.mines as f32 * 100.0 / self.num_cells() as f32).round() as u32
+            }
+        }
+    }
+
+    /// Returns whether the given [Toggle] is on.
+    pub fn is_on(&self, toggle: Toggle) -> bool {
+        match toggle {
+            Toggle::QuestionMarks => self.question_marks,
+            Toggle::NoGuess => self.no_guess,
+            Toggle::Practice => self.practice,
+        }
+    }
+
+    /// Flips the given [Toggle].
+    pub fn toggle(&mut self, toggle: Toggle) {
+        let value = match toggle {
+            Toggle::QuestionMarks => &mut self.question_marks,
+            Toggle::NoGuess => &mut self.no_guess,
+            Toggle::Practice => &mut self.practice,
+        };
+        *value = !*value;
+    }
+
+    /// Changes the given [Field] by the given amount, keeping it within its bounds. Shrinking the
+    /// board lowers the number of mines if they no longer fit.
+    pub fn adjust(&mut self, field: Field, delta: i32) {
+        let value = (self.get(field) as i64 + delta as i64).max(0) as u32;
+        match field {
+            Field::Width => {
+                self.width = value.clamp(MIN_GRID_WIDTH as u32, Dim::MAX as u32) as Dim;
+            }
+            Field::Height => self.height = value.clamp(1, Dim::MAX as u32) as Dim,
+            Field::Mines => self.mines = value.clamp(1, self.max_mines()) as Count,
+            Field::PercentMines => {
+                let mut mines =
+                    (self.num_cells() as f32 * value.min(100) as f32 / 100.0).round() as i64;
+                // On small boards a percent can be less than a mine, so always move at least one
+                if mines == self.mines as i64 {
+                    mines += delta.signum() as i64;
+                }
+                self.mines = mines.clamp(1, self.max_mines() as i64) as Count;
+            }
+        }
+        self.mines = self.mines.min(self.max_mines() as Count);
+    }
+
+    /// Applies the settings to the given game, starting a new game.
+    pub fn apply_to(&self, game: &mut Game) {
+        game.resize(self.width, self.height, self.mines);
+        game.question_marks = self.question_marks;
+        game.no_guess = self.no_guess;
+        game.practice = self.practice;
+    }
+
+    /// Returns the number of cells on the board.
+    fn num_cells(&self) -> u32 {
+        self.width as u32 * self.height as u32
+    }
+
+    /// Returns the most mines the board can hold that the display can still show.
+    fn max_mines(&self) -> u32 {
+        (self.num_cells() - 1).min(MAX_DISPLAYED_VALUE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mines_fit_the_display() {
+        let settings = Settings::of(&Game::new(255, 255, 13_000, Some(0)));
+        assert_eq!(settings.get(Field::Mines), MAX_DISPLAYED_VALUE);
+        let mut settings = Settings::of(&Game::new(9, 9, 10, Some(0)));
+        settings.adjust(Field::Width, 300);
+        settings.adjust(Field::Height, 300);
+        settings.adjust(Field::Mines, 5000);
+        assert_eq!(settings.get(Field::Mines), MAX_DISPLAYED_VALUE);
+        settings.adjust(Field::Width, -300);
+        settings.adjust(Field::Height, -300);
+        assert_eq!(settings.get(Field::Mines), MIN_GRID_WIDTH as u32 - 1);
+    }
+}
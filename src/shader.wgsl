@@ -14,6 +14,7 @@ struct VertexInput {
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) color: vec4<f32>,
 }
 
 struct InstanceInput {
@@ -21,6 +22,7 @@ struct InstanceInput {
     @location(6) vertex_scale: vec2<f32>,
     @location(7) tex_cord_translation: vec2<f32>,
     @location(8) tex_cord_scale: vec2<f32>,
+    @location(9) color: vec4<f32>,
 }
 
 @vertex
@@ -41,6 +43,7 @@ fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
     var out: VertexOutput;
     out.clip_position = scaling.view_proj * model_matrix * vec4<f32>(model.position, 0.0, 1.0);
     out.tex_coords = (tex_matrix * vec4<f32>(model.tex_coords, 0.0, 1.0)).xy;
+    out.color = instance.color;
     return out;
 }
 
@@ -53,5 +56,7 @@ var s_diffuse: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    // Instances with a color are drawn as a solid tint instead of the texture
+    let texture_color = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    return select(texture_color, in.color, in.color.a > 0.0);
 }
\ No newline at end of file
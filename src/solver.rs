@@ -0,0 +1,1021 @@
+use crate::minesweeper::{
+    CellImage,
+    Count,
+    Pos,
+};
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    fmt,
+    time::Instant,
+};
+
+/// Number of steps the enumeration of a single frontier component may take before giving up.
+/// Keeps the solver responsive on huge frontiers at the cost of missing some deductions.
+const MAX_ENUMERATION_STEPS: u32 = 1_000_000;
+/// Number of enumeration steps between checks of the deadline, since reading the clock every step
+/// would slow the search down.
+const DEADLINE_CHECK_STEPS: u32 = 1024;
+
+/// What has been proven about a hidden cell.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Deduction {
+    Safe,
+    Mine,
+}
+
+/// Why a [Deduction] holds. Positions are those of the revealed numbers the deduction was made
+/// from.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Reason {
+    /// The number has either all of its mines found or as many hidden neighbors as mines.
+    SingleCell(Pos),
+    /// The hidden neighbors of the first number are a subset of those of the second. The
+    /// difference in their numbers forces the cells only next to the second.
+    Subset(Pos, Pos),
+    /// The numbers share some hidden neighbors. The number of mines that can be in the shared
+    /// cells forces the cells next to only one of them.
+    Pairwise(Pos, Pos),
+    /// Every arrangement of mines along the frontier that fits the numbers and the mine count
+    /// agrees on the cell.
+    Enumeration,
+    /// The number of mines left forces every hidden cell away from the numbers.
+    MineCount,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reason::SingleCell(pos) => write!(
+                f,
+                "the number at {pos:?} forces all of its hidden neighbors"
+            ),
+            Reason::Subset(subset, superset) => write!(
+                f,
+                "the hidden neighbors of the number at {subset:?} are a subset of those of the \
+                number at {superset:?}"
+            ),
+            Reason::Pairwise(first, second) => write!(
+                f,
+                "the hidden neighbors shared by the numbers at {first:?} and {second:?} force \
+                the rest"
+            ),
+            Reason::Enumeration => write!(f, "every possible arrangement of mines agrees"),
+            Reason::MineCount => write!(f, "the number of mines left forces it"),
+        }
+    }
+}
+
+/// A cell that has been proven safe or proven to be a mine, along with the reason why.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Finding {
+    pub pos: Pos,
+    pub deduction: Deduction,
+    pub reason: Reason,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let deduction = match self.deduction {
+            Deduction::Safe => "safe",
+            Deduction::Mine => "a mine",
+        };
+        write!(f, "{:?} is {deduction} because {}", self.pos, self.reason)
+    }
+}
+
+/// A group of unknown cells and the number of mines among them. Derived from the number on a
+/// revealed cell.
+#[derive(Debug, PartialEq, Clone)]
+struct Constraint {
+    /// Position of the revealed number.
+    source: Pos,
+    /// Sorted so that constraints over the same cells can be compared.
+    cells: Vec<Pos>,
+    mines: u8,
+}
+
+impl Constraint {
+    /// Whether every cell in `self` is also in `other`.
+    fn is_subset_of(&self, other: &Constraint) -> bool {
+        self.cells.len() <= other.cells.len()
+            && self
+                .cells
+                .iter()
+                .all(|pos| other.cells.binary_search(pos).is_ok())
+    }
+
+    /// The cells in `self` that are not in `other`.
+    fn difference(&self, other: &Constraint) -> Vec<Pos> {
+        self.cells
+            .iter()
+            .filter(|pos| other.cells.binary_search(pos).is_err())
+            .copied()
+            .collect()
+    }
+}
+
+/// A connected group of frontier cells and every arrangement of mines among them that fits the
+/// numbers around them.
+struct Component {
+    cells: Vec<Pos>,
+    /// Whether the arrangements were fully enumerated. If not, `solutions` and `mine_counts` are
+    /// empty and any number of mines is assumed possible.
+    enumerated: bool,
+    /// `solutions[k]` is the number of arrangements with `k` mines.
+    solutions: Vec<f64>,
+    /// `mine_counts[k][i]` is the number of arrangements with `k` mines where `cells[i]` is a
+    /// mine.
+    mine_counts: Vec<Vec<f64>>,
+}
+
+impl Component {
+    /// Whether an arrangement with the given number of mines exists.
+    fn allows(&self, mines: usize) -> bool {
+        if self.enumerated {
+            self.solutions
+                .get(mines)
+                .is_some_and(|solutions| *solutions > 0.0)
+        } else {
+            mines <= self.cells.len()
+        }
+    }
+}
+
+/// Backtracking search over the arrangements of mines in a single frontier component.
+struct Enumerator {
+    /// Indices of the constraints each cell is part of.
+    cell_constraints: Vec<Vec<usize>>,
+    /// Number of mines each constraint still needs.
+    needed: Vec<u8>,
+    /// Number of cells in each constraint that are still unassigned.
+    unassigned: Vec<u8>,
+    assignment: Vec<bool>,
+    mines: usize,
+    max_mines: usize,
+    steps: u32,
+    /// Time to give up at, if any.
+    deadline: Option<Instant>,
+    component: Component,
+}
+
+impl Enumerator {
+    /// Assigns the cell at the given index and every cell after it in every valid way.
+    /// Returns false if the step limit or the deadline was reached.
+    fn search(&mut self, cell: usize) -> bool {
+        self.steps += 1;
+        if self.steps > MAX_ENUMERATION_STEPS {
+            return false;
+        }
+        if self.steps.is_multiple_of(DEADLINE_CHECK_STEPS)
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return false;
+        }
+        if cell == self.assignment.len() {
+            self.component.solutions[self.mines] += 1.0;
+            for (idx, is_mine) in self.assignment.iter().enumerate() {
+                if *is_mine {
+                    self.component.mine_counts[self.mines][idx] += 1.0;
+                }
+            }
+            return true;
+        }
+        for is_mine in [false, true] {
+            if is_mine && self.mines == self.max_mines {
+                continue;
+            }
+            // The cell can take this value if every constraint it is in can still be satisfied
+            let fits = self.cell_constraints[cell].iter().all(|&constraint| {
+                let needed = self.needed[constraint];
+                if is_mine {
+                    needed > 0
+                } else {
+                    needed < self.unassigned[constraint]
+                }
+            });
+            if !fits {
+                continue;
+            }
+            for &constraint in self.cell_constraints[cell].iter() {
+                self.unassigned[constraint] -= 1;
+                self.needed[constraint] -= is_mine as u8;
+            }
+            self.assignment[cell] = is_mine;
+            self.mines += is_mine as usize;
+            let finished = self.search(cell + 1);
+            self.mines -= is_mine as usize;
+            self.assignment[cell] = false;
+            for &constraint in self.cell_constraints[cell].iter() {
+                self.unassigned[constraint] += 1;
+                self.needed[constraint] += is_mine as u8;
+            }
+            if !finished {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Everything known about the grid while solving. Unknown cells are any cells that aren't
+/// showing a number and haven't been deduced yet.
+struct Knowledge<'a> {
+    images: &'a [Vec<CellImage>],
+    deduced: Vec<Vec<Option<Deduction>>>,
+    found: Vec<Finding>,
+    total_mines: Count,
+    /// Time to stop enumerating at, if any.
+    deadline: Option<Instant>,
+}
+
+impl<'a> Knowledge<'a> {
+    fn new(images: &'a [Vec<CellImage>], total_mines: Count) -> Self {
+        Self {
+            images,
+            deduced: images.iter().map(|row| vec![None; row.len()]).collect(),
+            found: Vec::new(),
+            total_mines,
+            deadline: None,
+        }
+    }
+
+    /// Whether the deadline has passed, so anything enumerated since may be incomplete.
+    fn past_deadline(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    fn height(&self) -> usize {
+        self.images.len()
+    }
+
+    fn width(&self) -> usize {
+        self.images.first().map_or(0, |row| row.len())
+    }
+
+    /// Whether the cell at the given [Pos] is hidden and hasn't been deduced yet.
+    fn is_unknown(&self, (row, col): Pos) -> bool {
+        self.images[row as usize][col as usize].number().is_none()
+            && self.deduced[row as usize][col as usize].is_none()
+    }
+
+    /// Returns the locations of all unknown cells.
+    fn unknown_cells(&self) -> Vec<Pos> {
+        (0..self.height())
+            .flat_map(|row| (0..self.width()).map(move |col| (row as u8, col as u8)))
+            .filter(|pos| self.is_unknown(*pos))
+            .collect()
+    }
+
+    /// Returns the number of mines that haven't been deduced yet.
+    fn mines_left(&self) -> usize {
+        let mines_found = self
+            .found
+            .iter()
+            .filter(|finding| finding.deduction == Deduction::Mine)
+            .count();
+        (self.total_mines as usize).saturating_sub(mines_found)
+    }
+
+    /// Records the given deduction. Returns whether it was new.
+    fn deduce(&mut self, pos: Pos, deduction: Deduction, reason: Reason) -> bool {
+        if self.is_unknown(pos) {
+            self.deduced[pos.0 as usize][pos.1 as usize] = Some(deduction);
+            self.found.push(Finding {
+                pos,
+                deduction,
+                reason,
+            });
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the locations of all cells adjacent to the given [Pos].
+    fn neighbors(&self, (row, col): Pos) -> impl Iterator<Item = Pos> {
+        let (height, width) = (self.height() as i16, self.width() as i16);
+        (-1..=1)
+            .flat_map(|row_difference| {
+                (-1..=1).map(move |col_difference| (row_difference, col_difference))
+            })
+            .filter(|difference| *difference != (0, 0))
+            .map(move |(row_difference, col_difference)| {
+                (row as i16 + row_difference, col as i16 + col_difference)
+            })
+            .filter(move |(row, col)| *row >= 0 && *row < height && *col >= 0 && *col < width)
+            .map(|(row, col)| (row as u8, col as u8))
+    }
+
+    /// Builds a [Constraint] for every revealed number with unknown neighbors. Neighbors already
+    /// deduced to be mines are subtracted from the number.
+    fn constraints(&self) -> Vec<Constraint> {
+        let mut result = Vec::new();
+        let mut seen = HashSet::new();
+        for row in 0..self.height() {
+            for col in 0..self.width() {
+                let pos = (row as u8, col as u8);
+                let Some(number) = self.images[row][col].number() else {
+                    continue;
+                };
+                let mut cells = Vec::new();
+                let mut mines = number;
+                for neighbor in self.neighbors(pos) {
+                    if self.is_unknown(neighbor) {
+                        cells.push(neighbor);
+                    } else if self.deduced[neighbor.0 as usize][neighbor.1 as usize]
+                        == Some(Deduction::Mine)
+                    {
+                        mines = mines.saturating_sub(1);
+                    }
+                }
+                cells.sort();
+                if !cells.is_empty() && seen.insert((cells.clone(), mines)) {
+                    result.push(Constraint {
+                        source: pos,
+                        cells,
+                        mines,
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    /// Deduces every cell of a constraint that is fully safe or fully mined.
+    /// Returns whether anything new was deduced.
+    fn apply_single_cell_rule(&mut self, constraints: &[Constraint]) -> bool {
+        let mut progress = false;
+        for constraint in constraints {
+            let deduction = if constraint.mines == 0 {
+                Deduction::Safe
+            } else if constraint.mines as usize == constraint.cells.len() {
+                Deduction::Mine
+            } else {
+                continue;
+            };
+            for pos in constraint.cells.iter() {
+                progress |= self.deduce(*pos, deduction, Reason::SingleCell(constraint.source));
+            }
+        }
+        progress
+    }
+
+    /// For every pair of constraints where one's cells are a subset of the other's, deduces the
+    /// cells only in the larger one if the difference in mines forces them.
+    /// Returns whether anything new was deduced.
+    fn apply_subset_rule(&mut self, constraints: &[Constraint]) -> bool {
+        let containing = constraints_containing(constraints);
+        let mut progress = false;
+        for (subset_idx, subset) in constraints.iter().enumerate() {
+            // Any superset has to contain the first cell of the subset
+            for &superset_idx in containing[&subset.cells[0]].iter() {
+                let superset = &constraints[superset_idx];
+                if superset_idx == subset_idx
+                    || superset.mines < subset.mines
+                    || !subset.is_subset_of(superset)
+                {
+                    continue;
+                }
+                let difference = superset.difference(subset);
+                let mines = (superset.mines - subset.mines) as usize;
+                let deduction = if mines == 0 {
+                    Deduction::Safe
+                } else if mines == difference.len() {
+                    Deduction::Mine
+                } else {
+                    continue;
+                };
+                let reason = Reason::Subset(subset.source, superset.source);
+                for pos in difference {
+                    progress |= self.deduce(pos, deduction, reason);
+                }
+            }
+        }
+        progress
+    }
+
+    /// For every pair of overlapping constraints, bounds the number of mines in the overlap and
+    /// deduces the cells outside of it if the bounds force them.
+    /// Returns whether anything new was deduced.
+    fn apply_pairwise_rule(&mut self, constraints: &[Constraint]) -> bool {
+        let containing = constraints_containing(constraints);
+        let mut progress = false;
+        for (first_idx, first) in constraints.iter().enumerate() {
+            let overlapping: HashSet<usize> = first
+                .cells
+                .iter()
+                .flat_map(|pos| containing[pos].iter().copied())
+                .filter(|idx| *idx > first_idx)
+                .collect();
+            for second_idx in overlapping {
+                let second = &constraints[second_idx];
+                let first_only = first.difference(second);
+                let second_only = second.difference(first);
+                let shared = first.cells.len() - first_only.len();
+                // Bounds on the number of mines in the shared cells
+                let most_shared = shared.min(first.mines as usize).min(second.mines as usize);
+                let least_shared = (first.mines as usize)
+                    .saturating_sub(first_only.len())
+                    .max((second.mines as usize).saturating_sub(second_only.len()));
+                let reason = Reason::Pairwise(first.source, second.source);
+                for (mines, only) in [(first.mines, first_only), (second.mines, second_only)] {
+                    // The cells next to only one number hold the mines the shared cells don't
+                    let mines = mines as usize;
+                    let deduction = if mines.saturating_sub(least_shared) == 0 {
+                        Deduction::Safe
+                    } else if mines.saturating_sub(most_shared) == only.len() {
+                        Deduction::Mine
+                    } else {
+                        continue;
+                    };
+                    for pos in only {
+                        progress |= self.deduce(pos, deduction, reason);
+                    }
+                }
+            }
+        }
+        progress
+    }
+
+    /// Deduces all unknown cells if the remaining mine count forces them.
+    /// Returns whether anything new was deduced.
+    fn apply_mine_count_rule(&mut self) -> bool {
+        let unknown = self.unknown_cells();
+        let mines_left = self.mines_left();
+        let deduction = if mines_left == 0 {
+            Deduction::Safe
+        } else if mines_left == unknown.len() {
+            Deduction::Mine
+        } else {
+            return false;
+        };
+        let mut progress = false;
+        for pos in unknown {
+            progress |= self.deduce(pos, deduction, Reason::MineCount);
+        }
+        progress
+    }
+
+    /// Splits the unknown cells next to numbers into independent components and enumerates every
+    /// arrangement of mines in each. Returns the components and the number of unknown cells not
+    /// next to any number.
+    fn frontier(&self) -> (Vec<Component>, usize) {
+        let constraints = self.constraints();
+        let containing = constraints_containing(&constraints);
+        let max_mines = self.mines_left();
+
+        let mut components = Vec::new();
+        let mut visited = vec![false; constraints.len()];
+        for start in 0..constraints.len() {
+            if visited[start] {
+                continue;
+            }
+            // Collect connected constraints and their cells in the order they were reached, so
+            // constraints fill up early in the search
+            visited[start] = true;
+            let mut queue = vec![start];
+            let mut cell_indices: HashMap<Pos, usize> = HashMap::new();
+            let mut cells = Vec::new();
+            let mut queue_idx = 0;
+            while queue_idx < queue.len() {
+                for pos in constraints[queue[queue_idx]].cells.iter() {
+                    if cell_indices.contains_key(pos) {
+                        continue;
+                    }
+                    cell_indices.insert(*pos, cells.len());
+                    cells.push(*pos);
+                    for &next in containing[pos].iter() {
+                        if !visited[next] {
+                            visited[next] = true;
+                            queue.push(next);
+                        }
+                    }
+                }
+                queue_idx += 1;
+            }
+
+            let mut cell_constraints = vec![Vec::new(); cells.len()];
+            for (local_idx, constraint_idx) in queue.iter().enumerate() {
+                for pos in constraints[*constraint_idx].cells.iter() {
+                    cell_constraints[cell_indices[pos]].push(local_idx);
+                }
+            }
+            let mut enumerator = Enumerator {
+                cell_constraints,
+                needed: queue.iter().map(|idx| constraints[*idx].mines).collect(),
+                unassigned: queue
+                    .iter()
+                    .map(|idx| constraints[*idx].cells.len() as u8)
+                    .collect(),
+                assignment: vec![false; cells.len()],
+                mines: 0,
+                max_mines,
+                steps: 0,
+                deadline: self.deadline,
+                component: Component {
+                    solutions: vec![0.0; cells.len() + 1],
+                    mine_counts: vec![vec![0.0; cells.len()]; cells.len() + 1],
+                    cells,
+                    enumerated: true,
+                },
+            };
+            if !enumerator.search(0) {
+                let component = &mut enumerator.component;
+                component.enumerated = false;
+                component.solutions.clear();
+                component.mine_counts.clear();
+            }
+            components.push(enumerator.component);
+        }
+
+        let frontier_size: usize = components
+            .iter()
+            .map(|component| component.cells.len())
+            .sum();
+        let outside = self.unknown_cells().len() - frontier_size;
+        (components, outside)
+    }
+
+    /// Enumerates every arrangement of mines along the frontier and deduces the cells every
+    /// arrangement agrees on, taking the number of mines left into account.
+    /// Returns whether anything new was deduced.
+    fn apply_enumeration_rule(&mut self) -> bool {
+        let (components, outside) = self.frontier();
+        let mines_left = self.mines_left();
+        // Whether the rest of the board can hold the mines left if the frontier holds `total`
+        let fits = |total: usize| total <= mines_left && mines_left - total <= outside;
+
+        let mut progress = false;
+        for (idx, component) in components.iter().enumerate() {
+            if !component.enumerated {
+                continue;
+            }
+            let others = reachable_mine_totals(&components, Some(idx));
+            let feasible: Vec<usize> = (0..component.solutions.len())
+                .filter(|mines| {
+                    component.allows(*mines)
+                        && (0..others.len()).any(|other| others[other] && fits(mines + other))
+                })
+                .collect();
+            let solutions: f64 = feasible
+                .iter()
+                .map(|mines| component.solutions[*mines])
+                .sum();
+            if solutions == 0.0 {
+                continue;
+            }
+            for (cell_idx, pos) in component.cells.iter().enumerate() {
+                let mine_solutions: f64 = feasible
+                    .iter()
+                    .map(|mines| component.mine_counts[*mines][cell_idx])
+                    .sum();
+                let deduction = if mine_solutions == 0.0 {
+                    Deduction::Safe
+                } else if mine_solutions == solutions {
+                    Deduction::Mine
+                } else {
+                    continue;
+                };
+                progress |= self.deduce(*pos, deduction, Reason::Enumeration);
+            }
+        }
+
+        // Cells away from the frontier are forced if every feasible total agrees on them
+        if outside > 0 {
+            let totals = reachable_mine_totals(&components, None);
+            let mut outside_mines = (0..totals.len())
+                .filter(|total| totals[*total] && fits(*total))
+                .map(|total| mines_left - total);
+            if let Some(first) = outside_mines.next()
+                && (first == 0 || first == outside)
+                && outside_mines.all(|mines| mines == first)
+            {
+                let deduction = if first == 0 {
+                    Deduction::Safe
+                } else {
+                    Deduction::Mine
+                };
+                let frontier: HashSet<Pos> = components
+                    .iter()
+                    .flat_map(|component| component.cells.iter().copied())
+                    .collect();
+                for pos in self.unknown_cells() {
+                    if !frontier.contains(&pos) {
+                        progress |= self.deduce(pos, deduction, Reason::MineCount);
+                    }
+                }
+            }
+        }
+        progress
+    }
+}
+
+/// Maps each cell to the indices of the constraints containing it.
+fn constraints_containing(constraints: &[Constraint]) -> HashMap<Pos, Vec<usize>> {
+    let mut containing: HashMap<Pos, Vec<usize>> = HashMap::new();
+    for (idx, constraint) in constraints.iter().enumerate() {
+        for pos in constraint.cells.iter() {
+            containing.entry(*pos).or_default().push(idx);
+        }
+    }
+    containing
+}
+
+/// Returns which total numbers of mines the given components can hold together, skipping the
+/// component at `excluded` if given. `result[total]` is whether `total` is reachable.
+fn reachable_mine_totals(components: &[Component], excluded: Option<usize>) -> Vec<bool> {
+    let mut reachable = vec![true];
+    for (idx, component) in components.iter().enumerate() {
+        if Some(idx) == excluded {
+            continue;
+        }
+        let mut next = vec![false; reachable.len() + component.cells.len()];
+        for total in (0..reachable.len()).filter(|total| reachable[*total]) {
+            for mines in (0..=component.cells.len()).filter(|mines| component.allows(*mines)) {
+                next[total + mines] = true;
+            }
+        }
+        reachable = next;
+    }
+    reachable
+}
+
+/// Returns every hidden cell that can be proven safe or proven to be a mine from the given
+/// images, as returned by [crate::minesweeper::Game::get_all_images], along with the reason for
+/// each. Flags and question marks are treated as hidden since they may be wrong.
+///
+/// Cheap rules are tried first: single numbers, subsets and pairs of overlapping numbers. Only
+/// once they stop finding anything is every arrangement of mines along the frontier enumerated.
+pub fn solve(images: &[Vec<CellImage>], total_mines: Count) -> Vec<Finding> {
+    solve_with_deadline(images, total_mines, None).unwrap()
+}
+
+/// Solves like [solve] but stops once the given time has passed, even in the middle of
+/// enumerating the frontier. Returns [None] if it ran out of time.
+pub fn solve_before(
+    images: &[Vec<CellImage>],
+    total_mines: Count,
+    deadline: Instant,
+) -> Option<Vec<Finding>> {
+    solve_with_deadline(images, total_mines, Some(deadline))
+}
+
+/// Solves like [solve], giving up and returning [None] once the given deadline, if any, passes.
+fn solve_with_deadline(
+    images: &[Vec<CellImage>],
+    total_mines: Count,
+    deadline: Option<Instant>,
+) -> Option<Vec<Finding>> {
+    let mut knowledge = Knowledge::new(images, total_mines);
+    knowledge.deadline = deadline;
+    loop {
+        let constraints = knowledge.constraints();
+        let progress = knowledge.apply_single_cell_rule(&constraints)
+            || knowledge.apply_subset_rule(&constraints)
+            || knowledge.apply_pairwise_rule(&constraints)
+            || knowledge.apply_mine_count_rule()
+            || knowledge.apply_enumeration_rule();
+        if knowledge.past_deadline() {
+            return None;
+        }
+        if !progress {
+            break;
+        }
+    }
+    Some(knowledge.found)
+}
+
+/// Convolves the given distributions of mine counts, skipping the one at `excluded` if given.
+/// `result[total]` is proportional to the number of ways the distributions can hold `total`
+/// mines together. Rescaled after every step to avoid overflow.
+fn convolve_mine_counts(distributions: &[Vec<f64>], excluded: Option<usize>) -> Vec<f64> {
+    let mut result = vec![1.0];
+    for (idx, distribution) in distributions.iter().enumerate() {
+        if Some(idx) == excluded {
+            continue;
+        }
+        let mut next = vec![0.0; result.len() + distribution.len() - 1];
+        for (total, ways) in result.iter().enumerate() {
+            for (mines, component_ways) in distribution.iter().enumerate() {
+                next[total + mines] += ways * component_ways;
+            }
+        }
+        let max = next.iter().copied().fold(0.0, f64::max);
+        if max > 0.0 {
+            next.iter_mut().for_each(|ways| *ways /= max);
+        }
+        result = next;
+    }
+    result
+}
+
+/// Returns the exact probability that each hidden cell is a mine, laid out like the given images
+/// as returned by [crate::minesweeper::Game::get_all_images]. Shown cells are [None]. Flags and
+/// question marks are treated as hidden since they may be wrong.
+///
+/// Every arrangement of mines along the frontier is weighted by the number of ways the rest of
+/// the mines can be placed in the hidden cells away from the numbers, so the total number of mines
+/// is accounted for. Returns [None] if the frontier is too large to enumerate or no arrangement
+/// fits the numbers.
+pub fn mine_probabilities(
+    images: &[Vec<CellImage>],
+    total_mines: Count,
+) -> Option<Vec<Vec<Option<f64>>>> {
+    let knowledge = Knowledge::new(images, total_mines);
+    let (components, outside) = knowledge.frontier();
+    if components.iter().any(|component| !component.enumerated) {
+        return None;
+    }
+    let mines = total_mines as usize;
+    let frontier_size: usize = components
+        .iter()
+        .map(|component| component.cells.len())
+        .sum();
+
+    // weights[total] is proportional to the number of ways to place the mines left off the
+    // frontier if the frontier holds `total` mines. Computed as logs to avoid overflow.
+    let mut ln_factorials = vec![0.0; outside + 1];
+    for n in 1..=outside {
+        ln_factorials[n] = ln_factorials[n - 1] + (n as f64).ln();
+    }
+    let ln_weights: Vec<Option<f64>> = (0..=frontier_size)
+        .map(|total| {
+            (total <= mines && mines - total <= outside).then(|| {
+                let off_frontier = mines - total;
+                ln_factorials[outside]
+                    - ln_factorials[off_frontier]
+                    - ln_factorials[outside - off_frontier]
+            })
+        })
+        .collect();
+    let max_ln_weight = ln_weights.iter().flatten().copied().reduce(f64::max)?;
+    let weights: Vec<f64> = ln_weights
+        .iter()
+        .map(|ln_weight| ln_weight.map_or(0.0, |ln_weight| (ln_weight - max_ln_weight).exp()))
+        .collect();
+
+    // Rescale each component so the largest count is 1. Every term of a probability contains
+    // exactly one count from each component, so this doesn't change the result.
+    let scales: Vec<f64> = components
+        .iter()
+        .map(|component| component.solutions.iter().copied().fold(0.0, f64::max))
+        .collect();
+    let distributions: Vec<Vec<f64>> = components
+        .iter()
+        .zip(scales.iter())
+        .map(|(component, scale)| {
+            component
+                .solutions
+                .iter()
+                .map(|solutions| solutions / scale)
+                .collect()
+        })
+        .collect();
+
+    let mut result: Vec<Vec<Option<f64>>> = images
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|image| image.number().is_none().then_some(0.0))
+                .collect()
+        })
+        .collect();
+
+    // Cells away from the frontier all share the expected number of mines left off it
+    let totals = convolve_mine_counts(&distributions, None);
+    let total_weight: f64 = (0..totals.len())
+        .map(|total| totals[total] * weights[total])
+        .sum();
+    if total_weight == 0.0 {
+        return None;
+    }
+    if outside > 0 {
+        let expected_mines: f64 = (0..totals.len())
+            .filter(|total| weights[*total] > 0.0)
+            .map(|total| totals[total] * weights[total] * (mines - total) as f64)
+            .sum::<f64>()
+            / total_weight;
+        for row in result.iter_mut() {
+            for probability in row.iter_mut().flatten() {
+                *probability = expected_mines / outside as f64;
+            }
+        }
+    }
+
+    for (idx, component) in components.iter().enumerate() {
+        let others = convolve_mine_counts(&distributions, Some(idx));
+        // weight_with[mines] is proportional to the number of ways the rest of the board can be
+        // filled if this component holds `mines` mines
+        let weight_with: Vec<f64> = (0..component.solutions.len())
+            .map(|mines| {
+                (0..others.len())
+                    .map(|other| others[other] * weights[mines + other])
+                    .sum()
+            })
+            .collect();
+        let component_weight: f64 = (0..weight_with.len())
+            .map(|mines| distributions[idx][mines] * weight_with[mines])
+            .sum();
+        for (cell_idx, (row, col)) in component.cells.iter().enumerate() {
+            let mine_weight: f64 = (0..weight_with.len())
+                .map(|mines| {
+                    component.mine_counts[mines][cell_idx] / scales[idx] * weight_with[mines]
+                })
+                .sum();
+            result[*row as usize][*col as usize] = Some(mine_weight / component_weight);
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds images from rows of digits for numbers and dots for hidden cells.
+    fn images(rows: &[&str]) -> Vec<Vec<CellImage>> {
+        rows.iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| match c {
+                        '0' => CellImage::Zero,
+                        '1' => CellImage::One,
+                        '2' => CellImage::Two,
+                        '3' => CellImage::Three,
+                        '4' => CellImage::Four,
+                        '5' => CellImage::Five,
+                        '6' => CellImage::Six,
+                        '7' => CellImage::Seven,
+                        '8' => CellImage::Eight,
+                        _ => CellImage::Hidden,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn finding(pos: Pos, deduction: Deduction, reason: Reason) -> Finding {
+        Finding {
+            pos,
+            deduction,
+            reason,
+        }
+    }
+
+    #[test]
+    fn single_cell() {
+        let findings = solve(&images(&["1."]), 1);
+        assert_eq!(
+            findings,
+            [finding((0, 1), Deduction::Mine, Reason::SingleCell((0, 0)))]
+        );
+    }
+
+    #[test]
+    fn subset() {
+        // The 1 at the bottom left only touches the two cells at the top left, so the 1 next to
+        // it has no mines to the right
+        let findings = solve(&images(&["...", "11."]), 2);
+        let reason = Reason::Subset((1, 0), (1, 1));
+        assert!(findings.contains(&finding((0, 2), Deduction::Safe, reason)));
+        assert!(findings.contains(&finding((1, 2), Deduction::Safe, reason)));
+    }
+
+    #[test]
+    fn pairwise() {
+        // The 1 allows at most one mine in the shared cells, so the 3 needs both of its own
+        let findings = solve(&images(&["....", ".31."]), 3);
+        let reason = Reason::Pairwise((1, 1), (1, 2));
+        for pos in [(0, 0), (1, 0)] {
+            assert!(findings.contains(&finding(pos, Deduction::Mine, reason)));
+        }
+        for pos in [(0, 3), (1, 3)] {
+            assert!(findings.contains(&finding(pos, Deduction::Safe, reason)));
+        }
+    }
+
+    #[test]
+    fn mine_count() {
+        assert_eq!(
+            solve(&images(&["..", ".."]), 0),
+            [(0, 0), (0, 1), (1, 0), (1, 1)].map(|pos| finding(
+                pos,
+                Deduction::Safe,
+                Reason::MineCount
+            ))
+        );
+        assert_eq!(
+            solve(&images(&[".."]), 2),
+            [(0, 0), (0, 1)].map(|pos| finding(pos, Deduction::Mine, Reason::MineCount))
+        );
+    }
+
+    #[test]
+    fn enumeration() {
+        // One mine has to satisfy both numbers, so it is in a shared cell and the corners are
+        // safe. Only the mine count rules out a mine in each corner instead.
+        let findings = solve(&images(&["...", "1.1"]), 1);
+        assert_eq!(
+            findings,
+            [(0, 0), (0, 2)].map(|pos| finding(pos, Deduction::Safe, Reason::Enumeration))
+        );
+        // With two mines and nowhere else to put them, they have to be in the corners instead
+        let findings = solve(&images(&["...", "1.1"]), 2);
+        for pos in [(0, 0), (0, 2)] {
+            assert!(findings.contains(&finding(pos, Deduction::Mine, Reason::Enumeration)));
+        }
+    }
+
+    #[test]
+    fn enumeration_limit() {
+        // A long chain of 4s, each with eight hidden neighbors, has far too many arrangements
+        let row = ".4".repeat(20) + ".";
+        let hidden = ".".repeat(row.len());
+        let images = images(&[&hidden, &row, &hidden]);
+        let knowledge = Knowledge::new(&images, 60);
+        let (components, outside) = knowledge.frontier();
+        assert_eq!(components.len(), 1);
+        assert!(!components[0].enumerated);
+        assert_eq!(outside, 0);
+        assert!(solve(&images, 60).is_empty());
+        assert!(mine_probabilities(&images, 60).is_none());
+    }
+
+    /// Asserts that the probabilities of the hidden cells are the given ones, with [None] for
+    /// shown cells.
+    fn assert_probabilities(
+        probabilities: Option<Vec<Vec<Option<f64>>>>,
+        expected: &[&[Option<f64>]],
+    ) {
+        let probabilities = probabilities.unwrap();
+        assert_eq!(probabilities.len(), expected.len());
+        for (row, expected_row) in probabilities.iter().zip(expected) {
+            assert_eq!(row.len(), expected_row.len());
+            for (probability, expected) in row.iter().zip(expected_row.iter()) {
+                match (probability, expected) {
+                    (Some(probability), Some(expected)) => {
+                        assert!(
+                            (probability - expected).abs() < 1e-9,
+                            "{row:?} != {expected_row:?}"
+                        )
+                    }
+                    _ => assert_eq!(probability, expected),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn one_two_one_probabilities() {
+        // The 2 can't have a mine above it without giving a 1 two mines
+        let probabilities = mine_probabilities(&images(&[".....", ".121."]), 2);
+        assert_probabilities(
+            probabilities,
+            &[
+                &[Some(0.0), Some(1.0), Some(0.0), Some(1.0), Some(0.0)],
+                &[Some(0.0), None, None, None, Some(0.0)],
+            ],
+        );
+    }
+
+    #[test]
+    fn weighted_probabilities() {
+        // The numbers share a cell, so they hold either one mine in it or two in the cells on
+        // either side. The three cells off the frontier hold the other mine in C(3, 1) = 3 ways or
+        // none in C(3, 0) = 1 way, so the arrangement with the shared mine is three times as
+        // likely. Cells off the frontier hold (3 * 1 + 1 * 0) / 4 mines between them on average.
+        let probabilities = mine_probabilities(&images(&[".1.1...."]), 2);
+        assert_probabilities(
+            probabilities,
+            &[&[
+                Some(0.25),
+                None,
+                Some(0.75),
+                None,
+                Some(0.25),
+                Some(0.25),
+                Some(0.25),
+                Some(0.25),
+            ]],
+        );
+    }
+
+    #[test]
+    fn mine_count_probabilities() {
+        // Without any numbers, only the mine count says anything about a cell
+        let probabilities = mine_probabilities(&images(&["...", "..."]), 2);
+        assert_probabilities(
+            probabilities,
+            &[&[Some(1.0 / 3.0); 3], &[Some(1.0 / 3.0); 3]],
+        );
+        // Off the frontier, the cells share the mines the frontier can't hold
+        let probabilities = mine_probabilities(&images(&["1..."]), 2);
+        assert_probabilities(probabilities, &[&[None, Some(1.0), Some(0.5), Some(0.5)]]);
+    }
+}
@@ -1,9 +1,22 @@
-use crate::minesweeper::{
-    Count,
-    Dim,
+use crate::{
+    config::{
+        self,
+        CONFIG_FILE_NAME,
+        Config,
+        Keybindings,
+    },
+    main_window_graphics::MIN_GRID_WIDTH,
+    minesweeper::{
+        Count,
+        Dim,
+    },
+    paths,
 };
 use std::{
     env,
+    fs,
+    io,
+    path::PathBuf,
     str::FromStr,
 };
 
@@ -12,15 +25,27 @@ const DEFAULT_WIDTH: Dim = 10;
 const DEFAULT_HEIGHT: Dim = 10;
 const DEFAULT_NUM_MINES: Count = 20;
 
-/// Text printed for --help
-const HELP_TEXT: &str = "Usage: minesweeper [OPTION] ...
+/// Presets available without a presets file as name, width, height, and number of mines.
+const BUILT_IN_PRESETS: [(&str, Dim, Dim, Count); 3] = [
+    ("beginner", 9, 9, 10),
+    ("intermediate", 16, 16, 40),
+    ("expert", 30, 16, 99),
+];
+/// Name of the file in the config directory user-defined presets are read from.
+const PRESETS_FILE_NAME: &str = "presets.txt";
+
+/// Returns the text printed for --help.
+fn help_text() -> String {
+    format!(
+        "Usage: minesweeper [OPTION] ...
 Launches a game of minesweeper
+Defaults can be changed in config.txt in the config directory, with options overriding them
 
 Options:
 --help
 \tprints this message
 -w --width <grid_width>
-\tsets the width of the minesweeper board, defaults to 10
+\tsets the width of the minesweeper board, defaults to 10, must be at least {MIN_GRID_WIDTH}
 -h --height <grid_height>
 \tsets the height of the minesweeper board, defaults to 10
 -m --mines <num_mines>
@@ -28,13 +53,71 @@ Options:
 \tcannot be used if --percent-mines is also used
 --percent_mines <percent_mines>
 \tsets what percent of the board will be mines
-\tcannot be used if -m or --mines is also used";
+\tcannot be used if -m or --mines is also used
+--beginner
+\tplays on a 9x9 board with 10 mines
+--intermediate
+\tplays on a 16x16 board with 40 mines
+--expert
+\tplays on a 30x16 board with 99 mines
+--preset <name>
+\tplays on a board defined in presets.txt in the config directory or a built-in one
+\teach line of presets.txt is a name, width, height, and number of mines, e.g. 'huge 50 30 300'
+\tpresets cannot be used with each other or with -w, -h, -m, or --percent-mines
+-s --seed <seed>
+\tsets the seed used to place mines, the same seed and first click always give the same board
+\tdefaults to a new random seed for every game
+--no-guess
+\tonly generates boards that can be solved from the first click without guessing
+\tgives up after too many tries or two seconds and says so, leaving a board that may need guessing
+--scale <scale>
+\tsets how many screen pixels each texture pixel takes up when the window opens
+--integer-scaling <true|false>
+\tsets whether the game is only scaled by whole numbers, with black bars filling the rest of
+\tthe window, defaults to false
+--skin <dir>
+\tdraws the game with the skin in the given directory instead of the built-in one
+\ta skin is an atlas PNG and a skin.txt listing where every sprite is in it
+--check-skin <dir>
+\tprints every problem with the skin in the given directory and exits, failing if there are any
+--question-marks <true|false>
+\tsets whether left clicking a flag turns it into a question mark, defaults to true
+--practice
+\tallows undoing and redoing moves with Ctrl+Z and Ctrl+Y
+--replay <file>
+\tplays back a recorded game instead of starting a new one, ignoring all other options
+\tspace plays and pauses, period steps, up and down change speed, left, right, and home seek
+--resume
+\tcontinues the game saved when quitting with escape, ignoring all other options
+--board <file>
+\tcontinues the game saved in the given file, in the same format as the one saved when quitting
+\tcannot be used with --resume
+--screenshot <out.png>
+\trenders the game to the given PNG file without opening a window and exits
+\tcombine with --board to render a saved game, uses --scale if set
+--records
+\tprints the best times for every board size and mine count won so far and exits
+--stats
+\tprints lifetime stats for every board size and mine count played so far and exits
+--json
+\tprints the stats as JSON instead, can only be used with --stats"
+    )
+}
 
 /// Wraps an error message with formatting text all error messages should have.
 fn wrap_error_msg(msg: String) -> String {
     format!("minesweeper: {msg}\nTry 'minesweeper --help' for more information.")
 }
 
+/// Prefixes the given error message with the line of the config file the value it is about was
+/// read from, if it was read from there.
+fn at_config_line(line: Option<usize>, msg: String) -> String {
+    match line {
+        Some(line) => format!("{CONFIG_FILE_NAME} line {line}: {msg}"),
+        None => msg,
+    }
+}
+
 /// A value read from the command line.
 /// Contains methods for updating the value given a [String] with the new value.
 /// Only intended to be written to once since writing to it multiple times would indicate multiple
@@ -43,6 +126,8 @@ struct ArgValue<T: FromStr> {
     name: &'static str,
     value: Option<T>,
     is_set: bool,
+    /// Line of the config file the value was read from, if it came from there.
+    config_line: Option<usize>,
 }
 
 impl<T: FromStr> ArgValue<T> {
@@ -53,6 +138,7 @@ impl<T: FromStr> ArgValue<T> {
             name,
             value,
             is_set: false,
+            config_line: None,
         }
     }
 
@@ -64,31 +150,173 @@ impl<T: FromStr> ArgValue<T> {
             Err(format!("{} already set", self.name))
         } else if let Some(arg) = arg {
             match arg.parse() {
-                Ok(val) => {
-                    self.value = Some(val);
-                    self.is_set = true;
-                    Ok(())
-                }
+                Ok(val) => self.set(val),
                 Err(_) => Err(format!("invalid value for flag {flag}: {arg}")),
             }
         } else {
             Err(format!("no value provided for flag {flag}"))
         }
     }
+
+    /// Replaces the default value with the given one from the config file and the line it is on,
+    /// if there is one, unless the value was set on the command line.
+    fn or_config(&mut self, config_value: Option<(T, usize)>) {
+        if let Some((value, line)) = config_value.filter(|_| !self.is_set) {
+            self.value = Some(value);
+            self.config_line = Some(line);
+        }
+    }
+
+    /// Updates the [ArgValue] to the given value.
+    /// Returns an error if `self` has already been updated before.
+    fn set(&mut self, val: T) -> Result<(), String> {
+        if self.is_set {
+            Err(format!("{} already set", self.name))
+        } else {
+            self.value = Some(val);
+            self.is_set = true;
+            Ok(())
+        }
+    }
+}
+
+/// Sets the width, height, and number of mines to the ones of the preset with the given name,
+/// looking through the built-in presets and then the presets file.
+/// Returns an error if there is no such preset or any of the values has already been set.
+fn apply_preset(
+    name: &str,
+    width: &mut ArgValue<Dim>,
+    height: &mut ArgValue<Dim>,
+    num_mines: &mut ArgValue<Count>,
+) -> Result<(), String> {
+    let built_in = BUILT_IN_PRESETS
+        .iter()
+        .find(|(preset, ..)| *preset == name)
+        .map(|&(_, width, height, num_mines)| (width, height, num_mines));
+    let (preset_width, preset_height, preset_num_mines) = match built_in {
+        Some(preset) => preset,
+        None => find_user_preset(name)?,
+    };
+    width
+        .set(preset_width)
+        .and_then(|_| height.set(preset_height))
+        .and_then(|_| num_mines.set(preset_num_mines))
+        .map_err(|err| format!("cannot use preset {name}: {err}"))
 }
 
-/// Gets the starting parameters for minesweeper (width, height, num_mines) from command line
-/// arguments or falling back to defaults. If there's an error parsing the command line args or
-/// `--help` is passed, returns a message in the form of a string instead.
-pub fn get_starting_params() -> Result<(Dim, Dim, Count), String> {
+/// Finds the preset with the given name in the presets file, returning its width, height, and
+/// number of mines.
+/// Returns an error if the file can't be read, has an invalid line, or has no such preset.
+fn find_user_preset(name: &str) -> Result<(Dim, Dim, Count), String> {
+    let path = paths::config_dir()
+        .ok_or("could not find the config directory to read presets from")?
+        .join(PRESETS_FILE_NAME);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(format!("could not read {}: {err}", path.display())),
+    };
+
+    // Every line is a preset, skipping empty lines and comments
+    for (idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let invalid = || {
+            format!(
+                "{} line {}: expected a name, width, height, and number of mines: {line}",
+                path.display(),
+                idx + 1
+            )
+        };
+        let [preset, width, height, num_mines] = fields[..] else {
+            return Err(invalid());
+        };
+        if preset == name {
+            return match (width.parse(), height.parse(), num_mines.parse()) {
+                (Ok(width), Ok(height), Ok(num_mines)) => Ok((width, height, num_mines)),
+                _ => Err(invalid()),
+            };
+        }
+    }
+    Err(format!("unknown preset: {name}"))
+}
+
+/// The parameters used to start a game of minesweeper.
+pub struct StartingParams {
+    pub width: Dim,
+    pub height: Dim,
+    pub num_mines: Count,
+    /// Seed used for every game if set. Each game picks a random seed otherwise.
+    pub seed: Option<u64>,
+    /// Whether boards should only be generated if they can be solved without guessing.
+    pub no_guess: bool,
+    /// Whether moves can be undone and redone.
+    pub practice: bool,
+    /// How many screen pixels each texture pixel takes up when the window opens, if set.
+    pub scale: Option<u32>,
+    /// Whether the game is only scaled by whole numbers, keeping every pixel the same size.
+    pub integer_scaling: bool,
+    /// Directory of the skin to draw with instead of the built-in one.
+    pub skin: Option<PathBuf>,
+    /// Directory of a skin to check instead of playing.
+    pub check_skin: Option<PathBuf>,
+    /// Whether left clicking a flag question marks it.
+    pub question_marks: bool,
+    pub keybindings: Keybindings,
+    /// Replay file to play back instead of starting a new game.
+    pub replay: Option<PathBuf>,
+    /// Whether to continue the game saved when quitting instead of starting a new one.
+    pub resume: bool,
+    /// Saved game to continue instead of starting a new one.
+    pub board: Option<PathBuf>,
+    /// File to render the game to instead of playing.
+    pub screenshot: Option<PathBuf>,
+    /// Whether to print the records instead of playing.
+    pub records: bool,
+    /// Whether to print the lifetime stats instead of playing.
+    pub stats: bool,
+    /// Whether the stats should be printed as JSON.
+    pub json: bool,
+}
+
+/// Gets the starting parameters for minesweeper from command line arguments or falling back to
+/// defaults. If there's an error parsing the command line args or `--help` is passed, returns a
+/// message in the form of a string instead.
+pub fn get_starting_params() -> Result<StartingParams, String> {
     // Get cmd line args, skipping program name
-    let mut args = env::args().skip(1);
+    parse_starting_params(env::args().skip(1), config::load)
+}
 
+/// Gets the starting parameters like [get_starting_params] from the given command line arguments,
+/// falling back to the [Config] from the given function. The config is only loaded once the
+/// arguments are known to be valid and not to be `--help`.
+fn parse_starting_params(
+    mut args: impl Iterator<Item = String>,
+    load_config: impl FnOnce() -> Result<Config, String>,
+) -> Result<StartingParams, String> {
     // Set defaults
     let mut width = ArgValue::new("width", Some(DEFAULT_WIDTH));
     let mut height = ArgValue::new("height", Some(DEFAULT_HEIGHT));
     let mut num_mines = ArgValue::new("num_mines", Some(DEFAULT_NUM_MINES));
     let mut percent_mines: ArgValue<f32> = ArgValue::new("percent_mines", None);
+    let mut seed: ArgValue<u64> = ArgValue::new("seed", None);
+    let mut no_guess = ArgValue::new("no_guess", Some(false));
+    let mut practice = ArgValue::new("practice", Some(false));
+    let mut scale: ArgValue<u32> = ArgValue::new("scale", None);
+    let mut integer_scaling = ArgValue::new("integer_scaling", Some(false));
+    let mut skin: ArgValue<PathBuf> = ArgValue::new("skin", None);
+    let mut check_skin: ArgValue<PathBuf> = ArgValue::new("check_skin", None);
+    let mut question_marks = ArgValue::new("question_marks", Some(true));
+    let mut replay: ArgValue<PathBuf> = ArgValue::new("replay", None);
+    let mut resume = ArgValue::new("resume", Some(false));
+    let mut board: ArgValue<PathBuf> = ArgValue::new("board", None);
+    let mut screenshot: ArgValue<PathBuf> = ArgValue::new("screenshot", None);
+    let mut records = ArgValue::new("records", Some(false));
+    let mut stats = ArgValue::new("stats", Some(false));
+    let mut json = ArgValue::new("json", Some(false));
 
     // Loop through args until end, error, or --help
     while let Some(arg) = args.next() {
@@ -97,12 +325,44 @@ pub fn get_starting_params() -> Result<(Dim, Dim, Count), String> {
             "-h" | "--height" => height.update(&arg, args.next()),
             "-m" | "--mines" => num_mines.update(&arg, args.next()),
             "-p" | "--percent-mines" => percent_mines.update(&arg, args.next()),
-            "--help" => return Err(HELP_TEXT.to_string()), // returns to prevent error wrapping
+            "-s" | "--seed" => seed.update(&arg, args.next()),
+            "--beginner" | "--intermediate" | "--expert" => {
+                apply_preset(&arg[2..], &mut width, &mut height, &mut num_mines)
+            }
+            "--preset" => match args.next() {
+                Some(name) => apply_preset(&name, &mut width, &mut height, &mut num_mines),
+                None => Err(format!("no value provided for flag {arg}")),
+            },
+            "--no-guess" => no_guess.set(true),
+            "--practice" => practice.set(true),
+            "--scale" => scale.update(&arg, args.next()),
+            "--integer-scaling" => integer_scaling.update(&arg, args.next()),
+            "--skin" => skin.update(&arg, args.next()),
+            "--check-skin" => check_skin.update(&arg, args.next()),
+            "--question-marks" => question_marks.update(&arg, args.next()),
+            "--replay" => replay.update(&arg, args.next()),
+            "--resume" => resume.set(true),
+            "--board" => board.update(&arg, args.next()),
+            "--screenshot" => screenshot.update(&arg, args.next()),
+            "--records" => records.set(true),
+            "--stats" => stats.set(true),
+            "--json" => json.set(true),
+            "--help" => return Err(help_text()), // returns to prevent error wrapping
             _ => Err(format!("unknown argument: {}", arg)),
         }
         .map_err(wrap_error_msg)? // wrap all error messages with some standard text
     }
 
+    // Fall back to the config file for values not set on the command line
+    let config = load_config().map_err(wrap_error_msg)?;
+    width.or_config(config.width);
+    height.or_config(config.height);
+    num_mines.or_config(config.mines);
+    scale.or_config(config.scale);
+    integer_scaling.or_config(config.integer_scaling);
+    skin.or_config(config.skin);
+    question_marks.or_config(config.question_marks);
+
     // Return an error if both num_mines and percent_mines were set with command line args
     if num_mines.is_set && percent_mines.is_set {
         return Err(wrap_error_msg(
@@ -110,6 +370,26 @@ pub fn get_starting_params() -> Result<(Dim, Dim, Count), String> {
         ));
     }
 
+    // Return an error if both saved games were asked for
+    if resume.is_set && board.is_set {
+        return Err(wrap_error_msg(
+            "cannot use both --resume and --board".to_string(),
+        ));
+    }
+
+    // Return an error if --json was used without --stats
+    if json.is_set && !stats.is_set {
+        return Err(wrap_error_msg(
+            "--json can only be used with --stats".to_string(),
+        ));
+    }
+
+    // Keep the config file lines values came from to point at them if they are invalid
+    let width_line = width.config_line;
+    let height_line = height.config_line;
+    let num_mines_line = num_mines.config_line.filter(|_| !percent_mines.is_set);
+    let scale_line = scale.config_line;
+
     // Get values for width and height for ease of use
     let width = width.value.unwrap();
     let height = height.value.unwrap();
@@ -123,12 +403,26 @@ pub fn get_starting_params() -> Result<(Dim, Dim, Count), String> {
     };
 
     // Return error if any value is too small
-    if width <= 7 {
-        Err(format!("width must be greater than 7: {}", width))
+    if width < MIN_GRID_WIDTH {
+        Err(at_config_line(
+            width_line,
+            format!("width must be at least {MIN_GRID_WIDTH}: {width}"),
+        ))
     } else if height == 0 {
-        Err(format!("height must be greater than 0: {}", height))
+        Err(at_config_line(
+            height_line,
+            format!("height must be greater than 0: {}", height),
+        ))
     } else if num_mines == 0 {
-        Err(format!("num mines must be greater than 0: {}", num_mines))
+        Err(at_config_line(
+            num_mines_line,
+            format!("num mines must be greater than 0: {}", num_mines),
+        ))
+    } else if scale.value == Some(0) {
+        Err(at_config_line(
+            scale_line,
+            "scale must be greater than 0: 0".to_string(),
+        ))
     } else {
         Ok(())
     }
@@ -137,11 +431,76 @@ pub fn get_starting_params() -> Result<(Dim, Dim, Count), String> {
     // Return error if grid has too many mines
     if (width as Count * height as Count) <= num_mines as Count {
         let num_cells = width as Count * height as Count;
-        return Err(wrap_error_msg(format!(
-            "num_mines must be less than num cells (width * height): \
-            {num_mines} < {num_cells} ({width} * {height})"
+        return Err(wrap_error_msg(at_config_line(
+            num_mines_line,
+            format!(
+                "num_mines must be less than num cells (width * height): \
+                {num_mines} < {num_cells} ({width} * {height})"
+            ),
         )));
     }
 
-    Ok((width, height, num_mines))
+    Ok(StartingParams {
+        width,
+        height,
+        num_mines,
+        seed: seed.value,
+        no_guess: no_guess.value.unwrap(),
+        practice: practice.value.unwrap(),
+        scale: scale.value,
+        integer_scaling: integer_scaling.value.unwrap(),
+        skin: skin.value,
+        check_skin: check_skin.value,
+        question_marks: question_marks.value.unwrap(),
+        keybindings: config.keybindings,
+        replay: replay.value,
+        resume: resume.value.unwrap(),
+        board: board.value,
+        screenshot: screenshot.value,
+        records: records.value.unwrap(),
+        stats: stats.value.unwrap(),
+        json: json.value.unwrap(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gets the starting parameters for the given command line arguments and config.
+    fn params(args: &[&str], config: Config) -> Result<StartingParams, String> {
+        parse_starting_params(args.iter().map(|arg| arg.to_string()), || Ok(config))
+    }
+
+    #[test]
+    fn invalid_config_values_name_their_line() {
+        let config = || Config {
+            width: Some((3, 2)),
+            mines: Some((900, 5)),
+            ..Default::default()
+        };
+        let err = params(&[], config()).err().unwrap();
+        assert!(
+            err.starts_with(&format!(
+                "minesweeper: config.txt line 2: width must be at least {MIN_GRID_WIDTH}: 3"
+            )),
+            "{err}"
+        );
+        let err = params(&["-w", "20"], config()).err().unwrap();
+        assert!(
+            err.starts_with("minesweeper: config.txt line 5: num_mines must be less than"),
+            "{err}"
+        );
+
+        // Values from the command line aren't blamed on the config file
+        let err = params(&["-w", "3"], config()).err().unwrap();
+        assert!(err.starts_with("minesweeper: width must be"), "{err}");
+        let err = params(&["-w", "20", "-m", "900"], config()).err().unwrap();
+        assert!(err.starts_with("minesweeper: num_mines must be"), "{err}");
+        let params = params(&["-w", "20", "-p", "10"], config()).unwrap();
+        assert_eq!(
+            (params.width, params.height, params.num_mines),
+            (20, 10, 20)
+        );
+    }
 }
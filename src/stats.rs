@@ -0,0 +1,428 @@
+use crate::{
+    paths,
+    records::Difficulty,
+};
+use anyhow::{
+    Context,
+    Result,
+    bail,
+    ensure,
+};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs,
+    io,
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+/// First line of every stats file, used to recognize the format.
+const STATS_HEADER: &str = "minesweeper stats 1";
+/// Name of the stats file inside the data directory.
+const STATS_FILE_NAME: &str = "stats.txt";
+/// Width of each bucket of the 3BV/s distribution.
+const BBBV_PER_SECOND_BUCKET_WIDTH: f64 = 0.5;
+/// Number of buckets in the 3BV/s distribution. The last one holds everything faster.
+const BBBV_PER_SECOND_BUCKETS: usize = 10;
+
+/// Lifetime statistics of the games played at one [Difficulty].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DifficultyStats {
+    /// Games started, including ones that were abandoned.
+    pub played: u32,
+    pub won: u32,
+    pub lost: u32,
+    /// Games won in a row since the last loss or abandoned game.
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    /// Time spent on every won game combined.
+    pub total_win_time: Duration,
+    /// How many won games were solved at each speed, in 3BV per second.
+    pub bbbv_per_second: [u32; BBBV_PER_SECOND_BUCKETS],
+}
+
+impl DifficultyStats {
+    /// Counts a game that was just started.
+    pub fn start(&mut self) {
+        self.played += 1;
+    }
+
+    /// Counts a game won in the given time on a board with the given 3BV.
+    pub fn win(&mut self, time: Duration, three_bv: u32) {
+        self.won += 1;
+        self.current_streak += 1;
+        self.longest_streak = self.longest_streak.max(self.current_streak);
+        self.total_win_time += time;
+        let bbbv_per_second = three_bv as f64 / time.as_secs_f64().max(f64::EPSILON);
+        let bucket = (bbbv_per_second / BBBV_PER_SECOND_BUCKET_WIDTH) as usize;
+        self.bbbv_per_second[bucket.min(BBBV_PER_SECOND_BUCKETS - 1)] += 1;
+    }
+
+    /// Counts a game that was lost.
+    pub fn lose(&mut self) {
+        self.lost += 1;
+        self.current_streak = 0;
+    }
+
+    /// Counts a game that was reset before it ended, which breaks the streak like a loss.
+    pub fn abandon(&mut self) {
+        self.current_streak = 0;
+    }
+
+    /// Returns the fraction of started games that were won, or [None] if none were started.
+    pub fn win_rate(&self) -> Option<f64> {
+        (self.played > 0).then(|| self.won as f64 / self.played as f64)
+    }
+
+    /// Returns the average time of won games, or [None] if none were won.
+    pub fn average_time(&self) -> Option<Duration> {
+        (self.won > 0).then(|| self.total_win_time / self.won)
+    }
+
+    /// Returns the range of 3BV/s counted by the bucket at the given index. The last bucket has no
+    /// upper bound.
+    fn bucket_range(index: usize) -> (f64, Option<f64>) {
+        let min = index as f64 * BBBV_PER_SECOND_BUCKET_WIDTH;
+        let max =
+            (index + 1 < BBBV_PER_SECOND_BUCKETS).then_some(min + BBBV_PER_SECOND_BUCKET_WIDTH);
+        (min, max)
+    }
+}
+
+/// Lifetime statistics for every difficulty that has been played.
+#[derive(Debug, Default)]
+pub struct Stats {
+    by_difficulty: BTreeMap<Difficulty, DifficultyStats>,
+}
+
+impl Stats {
+    /// Returns the path of the stats file.
+    pub fn path() -> Result<PathBuf> {
+        Ok(paths::data_dir()
+            .context("Could not find a directory to store stats in")?
+            .join(STATS_FILE_NAME))
+    }
+
+    /// Loads the stats from the stats file, or returns empty stats if it doesn't exist yet.
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        match fs::read_to_string(&path) {
+            Ok(contents) => contents
+                .parse()
+                .with_context(|| format!("Invalid stats file {}", path.display())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| format!("Could not read {}", path.display())),
+        }
+    }
+
+    /// Writes the stats to the stats file.
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Could not create directory {}", dir.display()))?;
+        }
+        fs::write(&path, self.to_string())
+            .with_context(|| format!("Could not write stats to {}", path.display()))
+    }
+
+    /// Returns the stats of the given difficulty, starting them if it was never played.
+    pub fn get_mut(&mut self, difficulty: Difficulty) -> &mut DifficultyStats {
+        self.by_difficulty.entry(difficulty).or_default()
+    }
+
+    /// Returns the stats as a human readable table, one section per difficulty.
+    pub fn table(&self) -> String {
+        if self.by_difficulty.is_empty() {
+            return "No stats yet, finish a game outside of practice mode to start them".into();
+        }
+        let mut table = String::new();
+        for (difficulty, stats) in &self.by_difficulty {
+            if !table.is_empty() {
+                table.push('\n');
+            }
+            table += &format!("{difficulty}\n");
+            table += &format!(
+                "Played {}, won {}, lost {}, win rate {}\n",
+                stats.played,
+                stats.won,
+                stats.lost,
+                stats
+                    .win_rate()
+                    .map_or("-".into(), |rate| format!("{:.1}%", rate * 100.0))
+            );
+            table += &format!(
+                "Current streak {}, longest streak {}, average time {}\n",
+                stats.current_streak,
+                stats.longest_streak,
+                stats
+                    .average_time()
+                    .map_or("-".into(), |time| format!("{:.3}s", time.as_secs_f64()))
+            );
+            if stats.won > 0 {
+                table += "3BV/s distribution:\n";
+                for (index, count) in stats.bbbv_per_second.iter().enumerate() {
+                    let range = match DifficultyStats::bucket_range(index) {
+                        (min, Some(max)) => format!("{min:.1}-{max:.1}"),
+                        (min, None) => format!("{min:.1}+"),
+                    };
+                    let bar = "#".repeat(*count as usize);
+                    table += format!("{range:>8} {count:>5} {bar}").trim_end();
+                    table.push('\n');
+                }
+            }
+        }
+        table.pop();
+        table
+    }
+
+    /// Returns the stats as a JSON array with an object per difficulty.
+    pub fn to_json(&self) -> String {
+        let json_option =
+            |value: Option<f64>| value.map_or("null".into(), |value| value.to_string());
+        let difficulties = self
+            .by_difficulty
+            .iter()
+            .map(|(difficulty, stats)| {
+                let buckets = stats
+                    .bbbv_per_second
+                    .iter()
+                    .enumerate()
+                    .map(|(index, count)| {
+                        let (min, max) = DifficultyStats::bucket_range(index);
+                        format!(
+                            "{{\"min\":{min},\"max\":{},\"count\":{count}}}",
+                            json_option(max)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                format!(
+                    "{{\"width\":{},\"height\":{},\"mines\":{},\"played\":{},\"won\":{},\
+                    \"lost\":{},\"win_rate\":{},\"current_streak\":{},\"longest_streak\":{},\
+                    \"average_time\":{},\"bbbv_per_second\":[{}]}}",
+                    difficulty.width,
+                    difficulty.height,
+                    difficulty.mines,
+                    stats.played,
+                    stats.won,
+                    stats.lost,
+                    json_option(stats.win_rate()),
+                    stats.current_streak,
+                    stats.longest_streak,
+                    json_option(stats.average_time().map(|time| time.as_secs_f64())),
+                    buckets.join(",")
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("[{}]", difficulties.join(","))
+    }
+}
+
+/// Writes the stats in the format of the stats file: a header followed by one line per
+/// difficulty of its played, won, lost, current streak, longest streak, and total win time
+/// followed by the counts of the 3BV/s distribution.
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{STATS_HEADER}")?;
+        for (difficulty, stats) in &self.by_difficulty {
+            write!(
+                f,
+                "{difficulty} {} {} {} {} {} {:.3}",
+                stats.played,
+                stats.won,
+                stats.lost,
+                stats.current_streak,
+                stats.longest_streak,
+                stats.total_win_time.as_secs_f64()
+            )?;
+            for count in stats.bbbv_per_second {
+                write!(f, " {count}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Stats {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut lines = s.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+        ensure!(
+            lines.next().map(|(_, line)| line) == Some(STATS_HEADER),
+            "not a stats file"
+        );
+
+        let mut stats = Self::default();
+        for (line_number, line) in lines.filter(|(_, line)| !line.is_empty()) {
+            let parse = || -> Result<(Difficulty, DifficultyStats)> {
+                let fields = line.split_whitespace().collect::<Vec<_>>();
+                let [
+                    difficulty,
+                    played,
+                    won,
+                    lost,
+                    current,
+                    longest,
+                    time,
+                    buckets @ ..,
+                ] = &fields[..]
+                else {
+                    bail!("expected difficulty, counts, streaks, time, and 3BV/s distribution");
+                };
+                let buckets = buckets
+                    .iter()
+                    .map(|count| count.parse::<u32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .context("invalid 3BV/s distribution")?;
+                let difficulty_stats = DifficultyStats {
+                    played: played.parse().context("invalid games played")?,
+                    won: won.parse().context("invalid games won")?,
+                    lost: lost.parse().context("invalid games lost")?,
+                    current_streak: current.parse().context("invalid current streak")?,
+                    longest_streak: longest.parse().context("invalid longest streak")?,
+                    total_win_time: time
+                        .parse::<f64>()
+                        .ok()
+                        .and_then(|time| Duration::try_from_secs_f64(time).ok())
+                        .context("invalid total win time")?,
+                    bbbv_per_second: buckets.try_into().map_err(|buckets: Vec<_>| {
+                        anyhow::anyhow!(
+                            "expected {BBBV_PER_SECOND_BUCKETS} 3BV/s buckets, found {}",
+                            buckets.len()
+                        )
+                    })?,
+                };
+                Ok((difficulty.parse()?, difficulty_stats))
+            };
+            let (difficulty, difficulty_stats) =
+                parse().with_context(|| format!("line {line_number}"))?;
+            stats.by_difficulty.insert(difficulty, difficulty_stats);
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEGINNER: Difficulty = Difficulty {
+        width: 9,
+        height: 9,
+        mines: 10,
+    };
+
+    #[test]
+    fn streaks() {
+        let mut stats = DifficultyStats::default();
+        for _ in 0..3 {
+            stats.start();
+            stats.win(Duration::from_secs(10), 10);
+        }
+        assert_eq!((stats.current_streak, stats.longest_streak), (3, 3));
+        stats.start();
+        stats.lose();
+        assert_eq!((stats.current_streak, stats.longest_streak), (0, 3));
+        stats.start();
+        stats.win(Duration::from_secs(10), 10);
+        stats.start();
+        stats.abandon();
+        assert_eq!((stats.current_streak, stats.longest_streak), (0, 3));
+        assert_eq!((stats.played, stats.won, stats.lost), (6, 4, 1));
+        assert_eq!(stats.win_rate(), Some(4.0 / 6.0));
+    }
+
+    #[test]
+    fn average_time() {
+        let mut stats = DifficultyStats::default();
+        assert_eq!(stats.average_time(), None);
+        assert_eq!(stats.win_rate(), None);
+        stats.win(Duration::from_secs(10), 10);
+        stats.win(Duration::from_millis(20_500), 10);
+        assert_eq!(stats.average_time(), Some(Duration::from_millis(15_250)));
+    }
+
+    #[test]
+    fn bbbv_per_second_buckets() {
+        let mut stats = DifficultyStats::default();
+        // 0.4, 0.5, 4.9, and 10 3BV/s, then a win taking no time at all
+        for three_bv in [4, 5, 49, 100] {
+            stats.win(Duration::from_secs(10), three_bv);
+        }
+        stats.win(Duration::ZERO, 1);
+        assert_eq!(stats.bbbv_per_second, [1, 1, 0, 0, 0, 0, 0, 0, 0, 3]);
+        assert_eq!(DifficultyStats::bucket_range(0), (0.0, Some(0.5)));
+        assert_eq!(DifficultyStats::bucket_range(9), (4.5, None));
+    }
+
+    #[test]
+    fn to_json() {
+        assert_eq!(Stats::default().to_json(), "[]");
+        let mut stats = Stats::default();
+        let beginner = stats.get_mut(BEGINNER);
+        beginner.start();
+        beginner.win(Duration::from_secs(20), 15);
+        beginner.start();
+        beginner.lose();
+        let expert = Difficulty {
+            width: 30,
+            height: 16,
+            mines: 99,
+        };
+        stats.get_mut(expert).start();
+        let buckets = |counts: [u32; BBBV_PER_SECOND_BUCKETS]| {
+            let mut json = String::new();
+            for (index, count) in counts.iter().enumerate() {
+                let min = ["0", "0.5", "1", "1.5", "2", "2.5", "3", "3.5", "4", "4.5"][index];
+                let max = [
+                    "0.5", "1", "1.5", "2", "2.5", "3", "3.5", "4", "4.5", "null",
+                ][index];
+                json += &format!("{{\"min\":{min},\"max\":{max},\"count\":{count}}},");
+            }
+            json.pop();
+            json
+        };
+        assert_eq!(
+            stats.to_json(),
+            format!(
+                "[{{\"width\":9,\"height\":9,\"mines\":10,\"played\":2,\"won\":1,\"lost\":1,\
+                 \"win_rate\":0.5,\"current_streak\":0,\"longest_streak\":1,\
+                 \"average_time\":20,\"bbbv_per_second\":[{}]}},\
+                 {{\"width\":30,\"height\":16,\"mines\":99,\"played\":1,\"won\":0,\"lost\":0,\
+                 \"win_rate\":0,\"current_streak\":0,\"longest_streak\":0,\
+                 \"average_time\":null,\"bbbv_per_second\":[{}]}}]",
+                buckets([0, 1, 0, 0, 0, 0, 0, 0, 0, 0]),
+                buckets([0; BBBV_PER_SECOND_BUCKETS])
+            )
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut stats = Stats::default();
+        let beginner = stats.get_mut(BEGINNER);
+        beginner.start();
+        beginner.win(Duration::from_millis(12_345), 20);
+        let text = stats.to_string();
+        assert_eq!(
+            text,
+            "minesweeper stats 1\n9x9/10 1 1 0 1 1 12.345 0 0 0 1 0 0 0 0 0 0\n"
+        );
+        let parsed: Stats = text.parse().unwrap();
+        assert_eq!(parsed.by_difficulty, stats.by_difficulty);
+
+        assert!("minesweeper stats 2\n".parse::<Stats>().is_err());
+        for line in [
+            "9x9/10 1 1 0 1 1 12.345 0 0 0 1 0 0 0 0 0",
+            "9x9/10 1 1 0 1 1 -1 0 0 0 1 0 0 0 0 0 0",
+            "9x9/10 1 1 0 1",
+        ] {
+            let text = format!("{STATS_HEADER}\n{line}\n");
+            assert!(text.parse::<Stats>().is_err(), "{line}");
+        }
+    }
+}